@@ -1,4 +1,6 @@
+use coord::Cartesian;
 use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
 use std::hash::{BuildHasher, Hash, Hasher};
 
 /// A container for pairs where order shouldn't be considered
@@ -31,3 +33,92 @@ impl<T: Eq + Hash> Hash for Pair<T> {
         hasher.write_u64(a.finish() ^ b.finish());
     }
 }
+
+/// Error for when computing a memoized value requires the value itself.
+/// `key` is the key that was revisited while still being computed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecursionError<K> {
+    pub key: K,
+}
+
+/// Cache of values computed recursively from each other, as in dynamic
+/// programming over a grid. Keys default to grid positions.
+#[derive(Clone, Debug)]
+pub struct Memo<V, K = Cartesian> {
+    values: HashMap<K, V>,
+    in_progress: HashSet<K>,
+}
+
+impl<V: Clone, K: Clone + Hash + Eq> Memo<V, K> {
+    pub fn new() -> Memo<V, K> {
+        Memo {
+            values: HashMap::new(),
+            in_progress: HashSet::new(),
+        }
+    }
+
+    /// The value computed for `key`, if any
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(key)
+    }
+
+    /// The cached value for `key`, or else the value computed by `f`, which
+    /// gets the memo to look up the values it depends on. Fails with
+    /// `RecursionError` if `f` depends on `key` itself, directly or through
+    /// other keys, rather than recursing forever.
+    pub fn get_or_compute(
+        &mut self,
+        key: K,
+        f: impl FnOnce(&mut Self) -> Result<V, RecursionError<K>>,
+    ) -> Result<V, RecursionError<K>> {
+        if let Some(value) = self.values.get(&key) {
+            return Ok(value.clone());
+        }
+        if !self.in_progress.insert(key.clone()) {
+            return Err(RecursionError { key });
+        }
+        let result = f(self);
+        self.in_progress.remove(&key);
+        let value = result?;
+        self.values.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+impl<V: Clone, K: Clone + Hash + Eq> Default for Memo<V, K> {
+    fn default() -> Memo<V, K> {
+        Memo::new()
+    }
+}
+
+#[test]
+fn test_memo() {
+    /* Paths from (0, 0) moving only right or down, avoiding (1, 1) */
+    fn paths(memo: &mut Memo<u64>, c: Cartesian) -> Result<u64, RecursionError<Cartesian>> {
+        memo.get_or_compute(c, |memo| {
+            if c.x < 0 || c.y < 0 || c == Cartesian::new(1, 1) {
+                Ok(0)
+            } else if c == Cartesian::new(0, 0) {
+                Ok(1)
+            } else {
+                Ok(paths(memo, c - Cartesian::new(1, 0))? + paths(memo, c - Cartesian::new(0, 1))?)
+            }
+        })
+    }
+
+    let mut memo = Memo::new();
+    assert_eq!(paths(&mut memo, Cartesian::new(2, 2)), Ok(2));
+    assert_eq!(memo.get(&Cartesian::new(2, 1)), Some(&1));
+    assert_eq!(
+        paths(&mut memo, Cartesian::new(20, 20)).unwrap(),
+        67156001220
+    );
+
+    /* A value depending on itself through another */
+    fn cyclic(memo: &mut Memo<u64, u32>, n: u32) -> Result<u64, RecursionError<u32>> {
+        memo.get_or_compute(n, |memo| cyclic(memo, (n + 1) % 3))
+    }
+    let mut memo = Memo::new();
+    assert_eq!(cyclic(&mut memo, 0), Err(RecursionError { key: 0 }));
+    assert_eq!(memo.get(&0), None);
+}
@@ -1,56 +1,497 @@
+use num;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::iter;
 use std::num::ParseIntError;
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use std::str::FromStr;
 
+/// Integer types usable as coordinate components
+pub trait Coord:
+    num::Integer
+    + num::Signed
+    + num::ToPrimitive
+    + num::NumCast
+    + num::CheckedAdd
+    + num::traits::Saturating
+    + Copy
+    + Hash
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+}
+
+impl<T> Coord for T where
+    T: num::Integer
+        + num::Signed
+        + num::ToPrimitive
+        + num::NumCast
+        + num::CheckedAdd
+        + num::traits::Saturating
+        + Copy
+        + Hash
+        + AddAssign
+        + SubAssign
+        + MulAssign
+{
+}
+
+/// A point in two dimensions, generic over the component type. Defaults to
+/// `i32`, use e.g. `Cartesian<i64>` for problems with large coordinates.
+/// With the `serde` feature enabled it is serialized as `{"x":1,"y":2}`.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-pub struct Cartesian {
-    pub x: i32,
-    pub y: i32,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cartesian<T = i32> {
+    pub x: T,
+    pub y: T,
 }
 
-impl Cartesian {
-    pub fn new(x: i32, y: i32) -> Cartesian {
+impl<T: Coord> Cartesian<T> {
+    pub fn new(x: T, y: T) -> Cartesian<T> {
         Cartesian { x: x, y: y }
     }
 
-    /// Creates a list of points around `self` excluding diagonal
-    pub fn neigh4(&self) -> Vec<Cartesian> {
+    /// Creates a list of points around `self` excluding diagonal. The order is
+    /// `(x - 1, y)`, `(x, y + 1)`, `(x + 1, y)`, `(x, y - 1)`, which with y
+    /// growing downwards is west, south, east and north.
+    pub fn neigh4(&self) -> Vec<Cartesian<T>> {
         let x = self.x;
         let y = self.y;
+        let one = T::one();
         vec![
-            Cartesian::new(x - 1, y),
-            Cartesian::new(x, y + 1),
-            Cartesian::new(x + 1, y),
-            Cartesian::new(x, y - 1),
+            Cartesian::new(x - one, y),
+            Cartesian::new(x, y + one),
+            Cartesian::new(x + one, y),
+            Cartesian::new(x, y - one),
         ]
     }
 
-    /// Creates a list of points around `self` including diagonal
-    pub fn neigh8(&self) -> Vec<Cartesian> {
+    /// Creates a list of points around `self` including diagonal. The order
+    /// starts at `(x - 1, y)` and goes around through `(x, y + 1)`, so with y
+    /// growing downwards it is counter clockwise starting west. Use
+    /// `neigh8_clockwise_from` when a specific order is needed.
+    pub fn neigh8(&self) -> Vec<Cartesian<T>> {
         let x = self.x;
         let y = self.y;
+        let one = T::one();
         vec![
-            Cartesian::new(x - 1, y),
-            Cartesian::new(x - 1, y + 1),
-            Cartesian::new(x, y + 1),
-            Cartesian::new(x + 1, y + 1),
-            Cartesian::new(x + 1, y),
-            Cartesian::new(x + 1, y - 1),
-            Cartesian::new(x, y - 1),
-            Cartesian::new(x - 1, y - 1),
+            Cartesian::new(x - one, y),
+            Cartesian::new(x - one, y + one),
+            Cartesian::new(x, y + one),
+            Cartesian::new(x + one, y + one),
+            Cartesian::new(x + one, y),
+            Cartesian::new(x + one, y - one),
+            Cartesian::new(x, y - one),
+            Cartesian::new(x - one, y - one),
         ]
     }
 
+    /// Points around `self` including diagonal, in clockwise order with y
+    /// growing downwards, beginning with the neighbor in direction `start`.
+    /// Suited for contour following, like Moore neighbor tracing.
+    pub fn neigh8_clockwise_from(&self, start: Direction) -> Vec<Cartesian<T>> {
+        const CLOCKWISE: [(i32, i32); 8] = [
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+        ];
+        let offset = match start {
+            Direction::North => 0,
+            Direction::East => 2,
+            Direction::South => 4,
+            Direction::West => 6,
+        };
+        (0..8)
+            .map(|i| {
+                let (dx, dy) = CLOCKWISE[(offset + i) % 8];
+                Cartesian::new(
+                    self.x + <T as num::NumCast>::from(dx).unwrap(),
+                    self.y + <T as num::NumCast>::from(dy).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    /// Points around `self` excluding diagonal, for which `pred` holds
+    pub fn neigh4_where(
+        &self,
+        pred: impl Fn(&Cartesian<T>) -> bool,
+    ) -> impl Iterator<Item = Cartesian<T>> {
+        self.neigh4().into_iter().filter(move |n| pred(n))
+    }
+
+    /// Points around `self` including diagonal, for which `pred` holds
+    pub fn neigh8_where(
+        &self,
+        pred: impl Fn(&Cartesian<T>) -> bool,
+    ) -> impl Iterator<Item = Cartesian<T>> {
+        self.neigh8().into_iter().filter(move |n| pred(n))
+    }
+
+    /// Points around `self` excluding diagonal, wrapping around the edges of a
+    /// `width` by `height` grid. Uses floored modulo, so `-1` wraps to `width - 1`.
+    pub fn neigh4_wrapped(&self, width: T, height: T) -> Vec<Cartesian<T>> {
+        self.neigh4()
+            .into_iter()
+            .map(|n| n.wrapped(width, height))
+            .collect()
+    }
+
+    /// Points around `self` including diagonal, wrapping around the edges of a
+    /// `width` by `height` grid, see `neigh4_wrapped`
+    pub fn neigh8_wrapped(&self, width: T, height: T) -> Vec<Cartesian<T>> {
+        self.neigh8()
+            .into_iter()
+            .map(|n| n.wrapped(width, height))
+            .collect()
+    }
+
+    fn wrapped(&self, width: T, height: T) -> Cartesian<T> {
+        Cartesian::new(self.x.mod_floor(&width), self.y.mod_floor(&height))
+    }
+
+    /// Creates a list of points a chess knight's move away from `self`
+    pub fn knight_moves(&self) -> Vec<Cartesian<T>> {
+        let one = T::one();
+        let two = one + one;
+        [(one, two), (two, one)]
+            .iter()
+            .flat_map(|&(dx, dy)| {
+                vec![
+                    Cartesian::new(dx, dy),
+                    Cartesian::new(dx, -dy),
+                    Cartesian::new(-dx, dy),
+                    Cartesian::new(-dx, -dy),
+                ]
+            })
+            .map(|delta| *self + delta)
+            .collect()
+    }
+
     /// Calculate the manhattan distance between two points
     pub fn manhattan_distance(&self, other: &Self) -> usize {
-        let x_dist = (self.x - other.x).abs() as usize;
-        let y_dist = (self.y - other.y).abs() as usize;
+        let x_dist = (self.x - other.x).abs().to_usize().unwrap();
+        let y_dist = (self.y - other.y).abs().to_usize().unwrap();
         x_dist + y_dist
     }
+
+    /// Calculate the chebyshev distance, i.e. number of king moves, between two points
+    pub fn chebyshev_distance(&self, other: &Self) -> usize {
+        let x_dist = (self.x - other.x).abs().to_usize().unwrap();
+        let y_dist = (self.y - other.y).abs().to_usize().unwrap();
+        x_dist.max(y_dist)
+    }
+
+    /// Calculate the euclidean distance between two points
+    pub fn euclidean_distance(&self, other: &Self) -> f64 {
+        let x_dist = (self.x - other.x).to_f64().unwrap();
+        let y_dist = (self.y - other.y).to_f64().unwrap();
+        x_dist.hypot(y_dist)
+    }
+
+    /// All integer points lying exactly on the segment between `self` and
+    /// `other`, excluding both ends. Unlike `line_to` nothing is approximated,
+    /// the segment is stepped by its direction reduced by the gcd of its
+    /// components.
+    pub fn lattice_points_between(&self, other: &Cartesian<T>) -> Vec<Cartesian<T>> {
+        let mut points = self.lattice_points_on(other);
+        points.pop();
+        if !points.is_empty() {
+            points.remove(0);
+        }
+        points
+    }
+
+    /// All integer points lying exactly on the segment between `self` and
+    /// `other`, including both ends, see `lattice_points_between`
+    pub fn lattice_points_on(&self, other: &Cartesian<T>) -> Vec<Cartesian<T>> {
+        let delta = *other - *self;
+        let steps = delta.x.gcd(&delta.y);
+        if steps.is_zero() {
+            return vec![*self];
+        }
+        let step = Cartesian::new(delta.x / steps, delta.y / steps);
+        num::range_inclusive(T::zero(), steps)
+            .map(|i| *self + step * i)
+            .collect()
+    }
+
+    /// Iterate over all points within manhattan distance `radius` of `self`,
+    /// in row-major order
+    pub fn manhattan_disk(&self, radius: usize) -> impl Iterator<Item = Cartesian<T>> {
+        let center = *self;
+        let r = <T as num::NumCast>::from(radius).unwrap();
+        num::range_inclusive(-r, r).flat_map(move |dy| {
+            let w = r - dy.abs();
+            num::range_inclusive(-w, w).map(move |dx| center + Cartesian::new(dx, dy))
+        })
+    }
+
+    /// Iterate over the points at exactly manhattan distance `radius` from
+    /// `self`, counter clockwise from `(x + radius, y)` when y grows upwards.
+    /// Only visits the `4 * radius` boundary points, rather than the whole disk.
+    pub fn manhattan_ring(&self, radius: usize) -> impl Iterator<Item = Cartesian<T>> {
+        let center = *self;
+        let r = <T as num::NumCast>::from(radius).unwrap();
+        let sides = if radius == 0 { 1 } else { 4 };
+        (0..sides).flat_map(move |side| {
+            num::range(T::zero(), r.max(T::one())).map(move |i| {
+                let delta = match side {
+                    0 => Cartesian::new(r - i, i),
+                    1 => Cartesian::new(-i, r - i),
+                    2 => Cartesian::new(i - r, -i),
+                    _ => Cartesian::new(i, i - r),
+                };
+                center + delta
+            })
+        })
+    }
+
+    /// All points on the line between `self` and `other`, including both ends,
+    /// approximated using Bresenham's line algorithm
+    pub fn line_to(&self, other: &Cartesian<T>) -> Vec<Cartesian<T>> {
+        let dx = (other.x - self.x).abs();
+        let dy = -(other.y - self.y).abs();
+        let step = (*other - *self).signum();
+        let mut err = dx + dy;
+        let mut current = *self;
+        let mut line = vec![current];
+
+        while current != *other {
+            let e2 = err + err;
+            if e2 >= dy {
+                err += dy;
+                current.x += step.x;
+            }
+            if e2 <= dx {
+                err += dx;
+                current.y += step.y;
+            }
+            line.push(current);
+        }
+        line
+    }
+
+    /// Iterate over all points in the rectangle spanned by `min` and `max`,
+    /// including both corners, in row-major order. Yields nothing if `min` is
+    /// greater than `max` along either axis.
+    pub fn rect_iter(min: &Cartesian<T>, max: &Cartesian<T>) -> impl Iterator<Item = Cartesian<T>> {
+        let (min, max) = (*min, *max);
+        num::range_inclusive(min.y, max.y).flat_map(move |y| {
+            num::range_inclusive(min.x, max.x).map(move |x| Cartesian::new(x, y))
+        })
+    }
+
+    /// Iterate over all points in a square spiral out from the origin, starting
+    /// `(0, 0)`, `(1, 0)`, `(1, 1)`, `(0, 1)`, `(-1, 1)`, `(-1, 0)`. The first
+    /// step is towards positive x, turning towards positive y, making the
+    /// spiral counter clockwise when y grows upwards.
+    pub fn spiral() -> impl Iterator<Item = Cartesian<T>> {
+        let (zero, one) = (T::zero(), T::one());
+        let steps = [
+            Cartesian::new(one, zero),
+            Cartesian::new(zero, one),
+            Cartesian::new(-one, zero),
+            Cartesian::new(zero, -one),
+        ];
+        let origin = Cartesian::new(zero, zero);
+        /* Legs go 1, 1, 2, 2, 3, 3, ... steps before turning */
+        let moves = (0..).flat_map(move |leg: usize| (0..leg / 2 + 1).map(move |_| steps[leg % 4]));
+        iter::once(origin).chain(moves.scan(origin, |p, step| {
+            *p += step;
+            Some(*p)
+        }))
+    }
+
+    /// Rotate 90 degrees clockwise around the origin, `(x, y) -> (y, -x)`.
+    /// Clockwise assumes y grows upwards, if y grows downwards, as in `Grid`,
+    /// this is a counter clockwise rotation on screen.
+    pub fn rotate_cw(&self) -> Cartesian<T> {
+        Cartesian::new(self.y, -self.x)
+    }
+
+    /// Rotate 90 degrees counter clockwise around the origin, `(x, y) -> (-y, x)`.
+    /// Counter clockwise assumes y grows upwards, see `rotate_cw`.
+    pub fn rotate_ccw(&self) -> Cartesian<T> {
+        Cartesian::new(-self.y, self.x)
+    }
+
+    /// Rotate `turns` quarter turns clockwise around `pivot`, in the same sense as `rotate_cw`
+    pub fn rotate_around(&self, pivot: &Cartesian<T>, turns: u8) -> Cartesian<T> {
+        let mut delta = *self - *pivot;
+        for _ in 0..turns % 4 {
+            delta = delta.rotate_cw();
+        }
+        *pivot + delta
+    }
+
+    /// Linearly interpolate between `self` at `t = 0.0` and `other` at `t = 1.0`.
+    /// Values of `t` outside of that range extrapolate along the line.
+    pub fn lerp(&self, other: &Cartesian<T>, t: f64) -> (f64, f64) {
+        let (x0, y0) = (self.x.to_f64().unwrap(), self.y.to_f64().unwrap());
+        let (x1, y1) = (other.x.to_f64().unwrap(), other.y.to_f64().unwrap());
+        (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t)
+    }
+
+    /// Interpolate like `lerp`, rounding to the nearest point with halfway
+    /// cases rounded away from zero
+    pub fn lerp_round(&self, other: &Cartesian<T>, t: f64) -> Cartesian<T> {
+        let (x, y) = self.lerp(other, t);
+        Cartesian::new(
+            <T as num::NumCast>::from(x.round()).unwrap(),
+            <T as num::NumCast>::from(y.round()).unwrap(),
+        )
+    }
+
+    /// Mirror across the x axis, `(x, y) -> (x, -y)`
+    pub fn reflect_x(&self) -> Cartesian<T> {
+        Cartesian::new(self.x, -self.y)
+    }
+
+    /// Mirror across the y axis, `(x, y) -> (-x, y)`
+    pub fn reflect_y(&self) -> Cartesian<T> {
+        Cartesian::new(-self.x, self.y)
+    }
+
+    /// Point reflection through `center`, i.e. a half turn around it
+    pub fn reflect_over(&self, center: &Cartesian<T>) -> Cartesian<T> {
+        *center + (*center - *self)
+    }
+
+    /// Add `other`, returning `None` if either component overflows
+    pub fn checked_add(&self, other: &Cartesian<T>) -> Option<Cartesian<T>> {
+        Some(Cartesian::new(
+            self.x.checked_add(&other.x)?,
+            self.y.checked_add(&other.y)?,
+        ))
+    }
+
+    /// Add `other`, clamping each component at the bounds of its type
+    pub fn saturating_add(&self, other: &Cartesian<T>) -> Cartesian<T> {
+        Cartesian::new(
+            self.x.saturating_add(other.x),
+            self.y.saturating_add(other.y),
+        )
+    }
+
+    /// Component-wise absolute value
+    pub fn abs(&self) -> Cartesian<T> {
+        Cartesian::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Component-wise minimum of `self` and `other`
+    pub fn min_components(&self, other: &Cartesian<T>) -> Cartesian<T> {
+        Cartesian::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    /// Component-wise maximum of `self` and `other`
+    pub fn max_components(&self, other: &Cartesian<T>) -> Cartesian<T> {
+        Cartesian::new(self.x.max(other.x), self.y.max(other.y))
+    }
+
+    /// Dot product of `self` and `other` seen as vectors
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product of `self` and `other` seen as
+    /// vectors. Positive if `other` is counter clockwise from `self` when y
+    /// grows upwards, negative if clockwise and zero if they are parallel.
+    pub fn cross(&self, other: &Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Reduce each component to -1, 0 or 1, giving the unit step towards `self`
+    pub fn signum(&self) -> Cartesian<T> {
+        Cartesian::new(self.x.signum(), self.y.signum())
+    }
+}
+
+/// Points are ordered by `y` first and then by `x`, i.e. in row-major order,
+/// so sorted points come out in reading order for a grid.
+impl<T: Ord> Ord for Cartesian<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.y, &self.x).cmp(&(&other.y, &other.x))
+    }
+}
+
+impl<T: Ord> PartialOrd for Cartesian<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats as `(x, y)`, which can be parsed back using `FromStr`
+impl<T: fmt::Display> fmt::Display for Cartesian<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl<T> From<(T, T)> for Cartesian<T> {
+    fn from((x, y): (T, T)) -> Cartesian<T> {
+        Cartesian { x, y }
+    }
+}
+
+impl<T: Copy> From<[T; 2]> for Cartesian<T> {
+    fn from(c: [T; 2]) -> Cartesian<T> {
+        Cartesian { x: c[0], y: c[1] }
+    }
+}
+
+impl<T> From<Cartesian<T>> for (T, T) {
+    fn from(c: Cartesian<T>) -> (T, T) {
+        (c.x, c.y)
+    }
 }
 
-impl FromStr for Cartesian {
-    type Err = ParseIntError;
+/// Error when parsing a `Cartesian` from a string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseCartesianError {
+    /// Fewer than two comma separated components
+    MissingComponent,
+    /// More than two comma separated components
+    TooManyComponents,
+    /// A component is not a valid integer
+    Int(ParseIntError),
+}
+
+impl From<ParseIntError> for ParseCartesianError {
+    fn from(e: ParseIntError) -> Self {
+        ParseCartesianError::Int(e)
+    }
+}
+
+impl fmt::Display for ParseCartesianError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseCartesianError::MissingComponent => write!(f, "missing coordinate component"),
+            ParseCartesianError::TooManyComponents => write!(f, "too many coordinate components"),
+            ParseCartesianError::Int(ref e) => write!(f, "invalid coordinate component: {}", e),
+        }
+    }
+}
+
+impl Error for ParseCartesianError {}
+
+impl<T> FromStr for Cartesian<T>
+where
+    T: Coord + FromStr<Err = ParseIntError>,
+{
+    type Err = ParseCartesianError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let coords: Vec<&str> = s
@@ -59,8 +500,14 @@ impl FromStr for Cartesian {
             .map(|t| t.trim())
             .collect();
 
-        let x_fromstr = coords[0].parse::<i32>()?;
-        let y_fromstr = coords[1].parse::<i32>()?;
+        if coords.len() < 2 {
+            return Err(ParseCartesianError::MissingComponent);
+        } else if coords.len() > 2 {
+            return Err(ParseCartesianError::TooManyComponents);
+        }
+
+        let x_fromstr = coords[0].parse::<T>()?;
+        let y_fromstr = coords[1].parse::<T>()?;
 
         Ok(Cartesian {
             x: x_fromstr,
@@ -69,10 +516,10 @@ impl FromStr for Cartesian {
     }
 }
 
-impl Add for Cartesian {
-    type Output = Cartesian;
+impl<T: Coord> Add for Cartesian<T> {
+    type Output = Cartesian<T>;
 
-    fn add(self, other: Cartesian) -> Cartesian {
+    fn add(self, other: Cartesian<T>) -> Cartesian<T> {
         Cartesian {
             x: self.x + other.x,
             y: self.y + other.y,
@@ -80,10 +527,10 @@ impl Add for Cartesian {
     }
 }
 
-impl<'a> Add for &'a Cartesian {
-    type Output = Cartesian;
+impl<'a, T: Coord> Add for &'a Cartesian<T> {
+    type Output = Cartesian<T>;
 
-    fn add(self, other: Self) -> Cartesian {
+    fn add(self, other: Self) -> Cartesian<T> {
         Cartesian {
             x: self.x + other.x,
             y: self.y + other.y,
@@ -91,24 +538,71 @@ impl<'a> Add for &'a Cartesian {
     }
 }
 
-impl AddAssign for Cartesian {
-    fn add_assign(&mut self, other: Cartesian) {
+impl<T: Coord> AddAssign for Cartesian<T> {
+    fn add_assign(&mut self, other: Cartesian<T>) {
         self.x += other.x;
         self.y += other.y;
     }
 }
 
-impl<'a> AddAssign<&'a Cartesian> for Cartesian {
-    fn add_assign(&mut self, other: &Cartesian) {
+impl<'a, T: Coord> AddAssign<&'a Cartesian<T>> for Cartesian<T> {
+    fn add_assign(&mut self, other: &Cartesian<T>) {
         self.x += other.x;
         self.y += other.y;
     }
 }
 
-impl Mul<i32> for Cartesian {
-    type Output = Cartesian;
+impl<T: Coord> Sub for Cartesian<T> {
+    type Output = Cartesian<T>;
+
+    fn sub(self, other: Cartesian<T>) -> Cartesian<T> {
+        Cartesian {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl<T: Coord> Sub for &Cartesian<T> {
+    type Output = Cartesian<T>;
+
+    fn sub(self, other: Self) -> Cartesian<T> {
+        Cartesian {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl<T: Coord> SubAssign for Cartesian<T> {
+    fn sub_assign(&mut self, other: Cartesian<T>) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+impl<T: Coord> SubAssign<&Cartesian<T>> for Cartesian<T> {
+    fn sub_assign(&mut self, other: &Cartesian<T>) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+impl<T: Coord> Neg for Cartesian<T> {
+    type Output = Cartesian<T>;
+
+    fn neg(self) -> Cartesian<T> {
+        Cartesian {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl<T: Coord> Mul<T> for Cartesian<T> {
+    type Output = Cartesian<T>;
 
-    fn mul(self, other: i32) -> Cartesian {
+    fn mul(self, other: T) -> Cartesian<T> {
         Cartesian {
             x: self.x * other,
             y: self.y * other,
@@ -116,10 +610,10 @@ impl Mul<i32> for Cartesian {
     }
 }
 
-impl Mul<i32> for &Cartesian {
-    type Output = Cartesian;
+impl<T: Coord> Mul<T> for &Cartesian<T> {
+    type Output = Cartesian<T>;
 
-    fn mul(self, other: i32) -> Cartesian {
+    fn mul(self, other: T) -> Cartesian<T> {
         Cartesian {
             x: self.x * other,
             y: self.y * other,
@@ -127,32 +621,1615 @@ impl Mul<i32> for &Cartesian {
     }
 }
 
-impl MulAssign<i32> for Cartesian {
-    fn mul_assign(&mut self, other: i32) {
+impl<T: Coord> MulAssign<T> for Cartesian<T> {
+    fn mul_assign(&mut self, other: T) {
         self.x *= other;
         self.y *= other;
     }
 }
 
-#[test]
-fn test_add() {
-    let a = Cartesian::new(1, 1);
-    let b = Cartesian::new(2, 2);
-    let c = Cartesian::new(3, 3);
-    let mut d = Cartesian::new(2, 2);
+/// Find the smallest rectangle containing all `points`, as its minimum and
+/// maximum corners. Returns `None` if there are no points.
+pub fn bounding_box<T: Coord>(points: &[Cartesian<T>]) -> Option<(Cartesian<T>, Cartesian<T>)> {
+    let first = *points.first()?;
+    Some(points.iter().fold((first, first), |(min, max), p| {
+        (min.min_components(p), max.max_components(p))
+    }))
+}
 
-    assert_eq!(&a + &b, c);
-    d += &a;
-    assert_eq!(&d, &c);
-    assert_eq!(a + b, c);
+/// An axis aligned rectangle of grid cells between two corners. Both corners
+/// are included, so rectangles whose edges touch, e.g. `max.x == other.min.x`,
+/// share a column of cells and intersect, while rectangles that are merely
+/// adjacent, e.g. `max.x + 1 == other.min.x`, do not.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Rect<T = i32> {
+    pub min: Cartesian<T>,
+    pub max: Cartesian<T>,
 }
 
-#[test]
-fn test_ops() {
-    let mut a = Cartesian::new(2, 2);
-    let b = Cartesian::new(10, 10);
+impl<T: Coord> Rect<T> {
+    /// Create the rectangle spanned by two opposite corners, in any order
+    pub fn new(a: Cartesian<T>, b: Cartesian<T>) -> Rect<T> {
+        Rect {
+            min: a.min_components(&b),
+            max: a.max_components(&b),
+        }
+    }
 
-    assert_eq!(&a * 5, b);
-    a *= 5;
-    assert_eq!(&a, &b);
+    pub fn contains(&self, p: &Cartesian<T>) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    /// Number of cells in the rectangle
+    pub fn area(&self) -> usize {
+        let size = self.max - self.min + Cartesian::new(T::one(), T::one());
+        (size.x * size.y).to_usize().unwrap()
+    }
+
+    /// The cells covered by both rectangles, or `None` if they are disjoint
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let min = self.min.max_components(&other.min);
+        let max = self.max.min_components(&other.max);
+        if min.x <= max.x && min.y <= max.y {
+            Some(Rect { min, max })
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all cells in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = Cartesian<T>> {
+        Cartesian::rect_iter(&self.min, &self.max)
+    }
+}
+
+/// A set of points for nearest neighbor queries by manhattan distance. The
+/// points are kept private, so the linear scan can later be replaced by a
+/// spatial index without changing users.
+#[derive(Clone, Debug)]
+pub struct PointSet<T = i32> {
+    points: Vec<Cartesian<T>>,
+}
+
+impl<T: Coord> PointSet<T> {
+    pub fn new(points: Vec<Cartesian<T>>) -> PointSet<T> {
+        PointSet { points }
+    }
+
+    /// The point closest to `query` along with its manhattan distance, or `None`
+    /// if the set is empty. Ties go to the point given first.
+    pub fn nearest(&self, query: &Cartesian<T>) -> Option<(&Cartesian<T>, usize)> {
+        self.points
+            .iter()
+            .map(|p| (p, p.manhattan_distance(query)))
+            .min_by_key(|&(_, d)| d)
+    }
+}
+
+/// Find the convex hull of `points` using Andrew's monotone chain algorithm.
+/// The hull vertices are returned in counter clockwise order, assuming y grows
+/// upwards, starting from the leftmost point. Points on the hull edges are left
+/// out, so collinear input gives just the two extremes. Fewer than three
+/// points are returned as they are.
+pub fn convex_hull<T: Coord>(points: &[Cartesian<T>]) -> Vec<Cartesian<T>> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|p| (p.x, p.y));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull: Vec<Cartesian<T>> = Vec::new();
+    /* Lower hull left to right, then upper hull right to left */
+    for pass in 0..2 {
+        let base = hull.len();
+        for &p in sorted.iter() {
+            while hull.len() >= base + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if (b - a).cross(&(p - a)) > T::zero() {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        /* The last point starts the next chain */
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// Twice the signed area of the polygon with the given `vertices`, positive if
+/// they are in counter clockwise order when y grows upwards
+fn double_signed_area<T: Coord>(vertices: &[Cartesian<T>]) -> T {
+    let mut area = T::zero();
+    for (i, a) in vertices.iter().enumerate() {
+        area += a.cross(&vertices[(i + 1) % vertices.len()]);
+    }
+    area
+}
+
+/// Area of the polygon with the given `vertices`, in either winding order,
+/// using the shoelace formula. The edge from the last vertex back to the first
+/// is implied.
+pub fn polygon_area<T: Coord>(vertices: &[Cartesian<T>]) -> f64 {
+    double_signed_area(vertices).abs().to_f64().unwrap() / 2.0
+}
+
+/// Number of integer points strictly inside the polygon with the given
+/// `vertices`, using Pick's theorem. Points on the boundary are not counted,
+/// add the number of points on the edges if those should be included. Fewer
+/// than three vertices, or all of them on a line, enclose nothing.
+pub fn lattice_points_inside<T: Coord>(vertices: &[Cartesian<T>]) -> usize {
+    let double_area = double_signed_area(vertices).abs().to_usize().unwrap();
+    if vertices.len() < 3 || double_area == 0 {
+        return 0;
+    }
+    let boundary: usize = vertices
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let d = vertices[(i + 1) % vertices.len()] - *a;
+            d.x.gcd(&d.y).to_usize().unwrap()
+        })
+        .sum();
+    (double_area + 2).saturating_sub(boundary) / 2
+}
+
+/// Check if `point` lies inside `polygon`, using the even-odd rule by casting
+/// a ray towards positive x. Points on the boundary count as inside, so the
+/// traced loop itself has to be excluded when only strictly enclosed points
+/// are wanted.
+pub fn point_in_polygon<T: Coord>(point: &Cartesian<T>, polygon: &[Cartesian<T>]) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let turn = (b - *a).cross(&(*point - *a));
+        if turn.is_zero()
+            && point.x >= a.x.min(b.x)
+            && point.x <= a.x.max(b.x)
+            && point.y >= a.y.min(b.y)
+            && point.y <= a.y.max(b.y)
+        {
+            return true;
+        }
+        /* Half open in y, so a ray through a vertex is only counted once */
+        if (a.y > point.y) != (b.y > point.y) && (turn > T::zero()) == (b.y > a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Number of times `loop_path` winds around `point`, positive for turns counter
+/// clockwise when y grows upwards, i.e. clockwise when it grows downwards. The
+/// edge from the last point back to the first is implied. Unlike the even-odd
+/// rule of `point_in_polygon`, loops crossing or overlapping themselves are
+/// counted consistently. Points on the loop itself give 0.
+pub fn winding_number<T: Coord>(point: &Cartesian<T>, loop_path: &[Cartesian<T>]) -> i32 {
+    let mut winding = 0;
+    for (i, a) in loop_path.iter().enumerate() {
+        let b = loop_path[(i + 1) % loop_path.len()];
+        let turn = (b - *a).cross(&(*point - *a));
+        if turn.is_zero()
+            && point.x >= a.x.min(b.x)
+            && point.x <= a.x.max(b.x)
+            && point.y >= a.y.min(b.y)
+            && point.y <= a.y.max(b.y)
+        {
+            return 0;
+        }
+        if a.y <= point.y && b.y > point.y && turn > T::zero() {
+            winding += 1;
+        } else if a.y > point.y && b.y <= point.y && turn < T::zero() {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// Check if `loop_path` strictly encloses `point`, i.e. winds around it at
+/// least once. Counting the enclosed points along with `lattice_points_inside`
+/// or `polygon_area` covers most loop puzzles.
+pub fn is_enclosed<T: Coord>(point: &Cartesian<T>, loop_path: &[Cartesian<T>]) -> bool {
+    winding_number(point, loop_path) != 0
+}
+
+/// Label every cell within the inclusive `bounds` with the index of the source
+/// closest to it by manhattan distance, or `None` if several sources are
+/// equally close. Sources themselves are labeled by their own index, unless
+/// given more than once.
+pub fn voronoi_regions<T: Coord>(
+    sources: &[Cartesian<T>],
+    bounds: (Cartesian<T>, Cartesian<T>),
+) -> HashMap<Cartesian<T>, Option<usize>> {
+    Cartesian::rect_iter(&bounds.0, &bounds.1)
+        .map(|cell| {
+            let mut closest = None;
+            let mut best = usize::MAX;
+            for (i, source) in sources.iter().enumerate() {
+                let distance = source.manhattan_distance(&cell);
+                if distance < best {
+                    best = distance;
+                    closest = Some(i);
+                } else if distance == best {
+                    closest = None;
+                }
+            }
+            (cell, closest)
+        })
+        .collect()
+}
+
+/// Number of cells owned by each source in regions from `voronoi_regions`.
+/// Sources owning no cells are left out, and so are tied cells.
+pub fn region_sizes<T: Coord>(
+    regions: &HashMap<Cartesian<T>, Option<usize>>,
+) -> HashMap<usize, usize> {
+    let mut sizes = HashMap::new();
+    for owner in regions.values().flatten() {
+        *sizes.entry(*owner).or_insert(0) += 1;
+    }
+    sizes
+}
+
+/// Mean position of `points`, or `None` if there are none
+pub fn centroid<T: Coord>(points: &[Cartesian<T>]) -> Option<(f64, f64)> {
+    if points.is_empty() {
+        return None;
+    }
+    let (x, y) = points.iter().fold((0.0, 0.0), |(x, y), p| {
+        (x + p.x.to_f64().unwrap(), y + p.y.to_f64().unwrap())
+    });
+    let n = points.len() as f64;
+    Some((x / n, y / n))
+}
+
+/// Approximate the point minimizing the total euclidean distance to `points`,
+/// using Weiszfeld's algorithm starting from the centroid. Iterates until a
+/// step moves the estimate less than `1e-9`, or for at most 1000 iterations.
+/// For total manhattan distance the median x and median y is optimal instead,
+/// and needs no approximation. `None` if there are no points.
+pub fn geometric_median<T: Coord>(points: &[Cartesian<T>]) -> Option<(f64, f64)> {
+    const MAX_ITERATIONS: usize = 1000;
+    const TOLERANCE: f64 = 1e-9;
+
+    let mut estimate = centroid(points)?;
+    let points = points
+        .iter()
+        .map(|p| (p.x.to_f64().unwrap(), p.y.to_f64().unwrap()))
+        .collect::<Vec<_>>();
+    for _ in 0..MAX_ITERATIONS {
+        let (mut x, mut y, mut weights) = (0.0, 0.0, 0.0);
+        for &(px, py) in &points {
+            let distance = (px - estimate.0).hypot(py - estimate.1);
+            /* Points at the estimate would get infinite weight, skip them */
+            if distance < TOLERANCE {
+                continue;
+            }
+            x += px / distance;
+            y += py / distance;
+            weights += 1.0 / distance;
+        }
+        if weights == 0.0 {
+            break;
+        }
+        let next = (x / weights, y / weights);
+        let step = (next.0 - estimate.0).hypot(next.1 - estimate.1);
+        estimate = next;
+        if step < TOLERANCE {
+            break;
+        }
+    }
+    Some(estimate)
+}
+
+/// Draw `points` as rows of `on` and `off` characters covering their bounding
+/// box, with y growing downwards. Rows are separated by newlines.
+pub fn render<T: Coord>(points: &HashSet<Cartesian<T>>, on: char, off: char) -> String {
+    let (min, max) = match bounding_box(&points.iter().cloned().collect::<Vec<_>>()) {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    num::range_inclusive(min.y, max.y)
+        .map(|y| {
+            num::range_inclusive(min.x, max.x)
+                .map(|x| {
+                    if points.contains(&Cartesian::new(x, y)) {
+                        on
+                    } else {
+                        off
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Map every character of a newline separated block of text to its position,
+/// with `(0, 0)` at the top left and y growing downwards. Lines may differ in
+/// length, and a trailing newline adds no row.
+pub fn parse_grid(s: &str) -> HashMap<Cartesian, char> {
+    s.lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .map(move |(x, c)| (Cartesian::new(x as i32, y as i32), c))
+        })
+        .collect()
+}
+
+/// Format `path` as its points joined by `->`, like `(0, 0)->(1, 0)->(1, 1)`.
+/// An empty path gives an empty string. Parsed back by `parse_path`.
+pub fn path_to_string<T: Coord + fmt::Display>(path: &[Cartesian<T>]) -> String {
+    path.iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join("->")
+}
+
+/// Parse a path formatted by `path_to_string`. Whitespace around the points is
+/// ignored, and an empty string gives an empty path.
+pub fn parse_path<T>(s: &str) -> Result<Vec<Cartesian<T>>, ParseCartesianError>
+where
+    T: Coord + FromStr<Err = ParseIntError>,
+{
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split("->").map(|p| p.trim().parse()).collect()
+}
+
+/// The first point occurring a second time in `path`. Consumes the iterator
+/// only up to that point, so it works on lazily generated paths.
+pub fn first_revisit<T: Coord>(
+    mut path: impl Iterator<Item = Cartesian<T>>,
+) -> Option<Cartesian<T>> {
+    let mut seen = HashSet::new();
+    path.find(|&p| !seen.insert(p))
+}
+
+/// A facing direction on a grid where y grows downwards, so `North` is `(0, -1)`
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+/// Error for text not describing a `Direction` or `HexDirection`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseDirectionError;
+
+impl Direction {
+    /// All directions, in clockwise order starting with `North`
+    pub fn all() -> impl Iterator<Item = Direction> {
+        [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ]
+        .iter()
+        .cloned()
+    }
+
+    /// The step taken when moving one cell in this direction
+    pub fn delta(&self) -> Cartesian {
+        match *self {
+            Direction::North => Cartesian::new(0, -1),
+            Direction::East => Cartesian::new(1, 0),
+            Direction::South => Cartesian::new(0, 1),
+            Direction::West => Cartesian::new(-1, 0),
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        match *self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        match *self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match *self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+/// Accepts `U`, `R`, `D`, `L` as well as the arrows `^`, `>`, `v`, `<`
+impl TryFrom<char> for Direction {
+    type Error = ParseDirectionError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            'U' | '^' => Ok(Direction::North),
+            'R' | '>' => Ok(Direction::East),
+            'D' | 'v' => Ok(Direction::South),
+            'L' | '<' => Ok(Direction::West),
+            _ => Err(ParseDirectionError),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Direction::try_from(c),
+            _ => Err(ParseDirectionError),
+        }
+    }
+}
+
+/// Error when a `Turtle` is given commands it can't follow
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseTurtleError {
+    /// A command not starting with `L`, `R` or `F`
+    UnknownCommand(char),
+    /// The step count following a command is not a valid integer
+    Steps(ParseIntError),
+}
+
+impl From<ParseIntError> for ParseTurtleError {
+    fn from(e: ParseIntError) -> Self {
+        ParseTurtleError::Steps(e)
+    }
+}
+
+impl fmt::Display for ParseTurtleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseTurtleError::UnknownCommand(c) => write!(f, "unknown turtle command: {}", c),
+            ParseTurtleError::Steps(ref e) => write!(f, "invalid step count: {}", e),
+        }
+    }
+}
+
+impl Error for ParseTurtleError {}
+
+/// A walker on a grid, keeping track of its position and facing direction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Turtle {
+    pub position: Cartesian,
+    pub facing: Direction,
+}
+
+impl Turtle {
+    pub fn new(position: Cartesian, facing: Direction) -> Turtle {
+        Turtle { position, facing }
+    }
+
+    /// Walk `n` steps forward, returning every cell entered along the way
+    pub fn forward(&mut self, n: usize) -> Vec<Cartesian> {
+        let mut visited = Vec::with_capacity(n);
+        for _ in 0..n {
+            self.position += self.facing.delta();
+            visited.push(self.position);
+        }
+        visited
+    }
+
+    pub fn turn_left(&mut self) {
+        self.facing = self.facing.turn_left();
+    }
+
+    pub fn turn_right(&mut self) {
+        self.facing = self.facing.turn_right();
+    }
+
+    /// Follow comma or whitespace separated commands like `R5, L2, F3`, where
+    /// `L` and `R` turn before walking and `F` walks straight ahead. Returns
+    /// every visited cell, starting with the current position, so revisits
+    /// can be found. Commands before a malformed one are still followed.
+    pub fn run(&mut self, commands: &str) -> Result<Vec<Cartesian>, ParseTurtleError> {
+        let mut visited = vec![self.position];
+        for command in commands.split(|c: char| c == ',' || c.is_whitespace()) {
+            let mut chars = command.chars();
+            match chars.next() {
+                Some('L') => self.turn_left(),
+                Some('R') => self.turn_right(),
+                Some('F') => {}
+                Some(c) => return Err(ParseTurtleError::UnknownCommand(c)),
+                None => continue,
+            }
+            let steps = chars.as_str().parse()?;
+            visited.extend(self.forward(steps));
+        }
+        Ok(visited)
+    }
+}
+
+/// A point in three dimensions
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Cartesian3 {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Cartesian3 {
+    pub fn new(x: i32, y: i32, z: i32) -> Cartesian3 {
+        Cartesian3 { x, y, z }
+    }
+
+    /// Creates a list of the points sharing a face with `self`
+    pub fn neigh6(&self) -> Vec<Cartesian3> {
+        let (x, y, z) = (self.x, self.y, self.z);
+        vec![
+            Cartesian3::new(x - 1, y, z),
+            Cartesian3::new(x + 1, y, z),
+            Cartesian3::new(x, y - 1, z),
+            Cartesian3::new(x, y + 1, z),
+            Cartesian3::new(x, y, z - 1),
+            Cartesian3::new(x, y, z + 1),
+        ]
+    }
+
+    /// Creates a list of all points around `self`, including diagonals
+    pub fn neigh26(&self) -> Vec<Cartesian3> {
+        let mut neighbors = Vec::with_capacity(26);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if (dx, dy, dz) != (0, 0, 0) {
+                        neighbors.push(Cartesian3::new(self.x + dx, self.y + dy, self.z + dz));
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// Calculate the manhattan distance between two points
+    pub fn manhattan_distance(&self, other: &Self) -> usize {
+        let x_dist = (self.x - other.x).unsigned_abs() as usize;
+        let y_dist = (self.y - other.y).unsigned_abs() as usize;
+        let z_dist = (self.z - other.z).unsigned_abs() as usize;
+        x_dist + y_dist + z_dist
+    }
+}
+
+impl FromStr for Cartesian3 {
+    type Err = ParseCartesianError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords: Vec<&str> = s
+            .trim_matches(|p| p == '(' || p == ')')
+            .split(',')
+            .map(|t| t.trim())
+            .collect();
+
+        if coords.len() < 3 {
+            return Err(ParseCartesianError::MissingComponent);
+        } else if coords.len() > 3 {
+            return Err(ParseCartesianError::TooManyComponents);
+        }
+
+        Ok(Cartesian3 {
+            x: coords[0].parse::<i32>()?,
+            y: coords[1].parse::<i32>()?,
+            z: coords[2].parse::<i32>()?,
+        })
+    }
+}
+
+impl Add for Cartesian3 {
+    type Output = Cartesian3;
+
+    fn add(self, other: Cartesian3) -> Cartesian3 {
+        Cartesian3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Add for &Cartesian3 {
+    type Output = Cartesian3;
+
+    fn add(self, other: Self) -> Cartesian3 {
+        Cartesian3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Cartesian3 {
+    type Output = Cartesian3;
+
+    fn sub(self, other: Cartesian3) -> Cartesian3 {
+        Cartesian3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Sub for &Cartesian3 {
+    type Output = Cartesian3;
+
+    fn sub(self, other: Self) -> Cartesian3 {
+        Cartesian3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+/// A hexagon on a grid of pointy topped hexagons, in axial coordinates. `q`
+/// grows towards east and `r` towards south east, so rows of constant `r` run
+/// east to west.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Hex {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl Hex {
+    pub fn new(q: i32, r: i32) -> Hex {
+        Hex { q, r }
+    }
+
+    /// The six hexagons sharing an edge with `self`, in the order of
+    /// `HexDirection::all`
+    pub fn neighbors(&self) -> Vec<Hex> {
+        HexDirection::all().map(|d| *self + d.delta()).collect()
+    }
+
+    /// Number of steps between two hexagons
+    pub fn distance(&self, other: &Hex) -> usize {
+        let dq = self.q - other.q;
+        let dr = self.r - other.r;
+        ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as usize
+    }
+
+    /// Position on a square grid for rendering, where every other cell of a
+    /// row is used and rows are offset by half a hexagon, `(2q + r, r)`
+    pub fn to_cartesian(&self) -> Cartesian {
+        Cartesian::new(2 * self.q + self.r, self.r)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, other: Hex) -> Hex {
+        Hex::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+/// A step between neighboring pointy topped hexagons
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum HexDirection {
+    East,
+    NorthEast,
+    NorthWest,
+    West,
+    SouthWest,
+    SouthEast,
+}
+
+impl HexDirection {
+    /// All directions, counter clockwise starting from `East`
+    pub fn all() -> impl Iterator<Item = HexDirection> {
+        [
+            HexDirection::East,
+            HexDirection::NorthEast,
+            HexDirection::NorthWest,
+            HexDirection::West,
+            HexDirection::SouthWest,
+            HexDirection::SouthEast,
+        ]
+        .iter()
+        .cloned()
+    }
+
+    /// The offset of a single step in this direction
+    pub fn delta(&self) -> Hex {
+        match *self {
+            HexDirection::East => Hex::new(1, 0),
+            HexDirection::NorthEast => Hex::new(1, -1),
+            HexDirection::NorthWest => Hex::new(0, -1),
+            HexDirection::West => Hex::new(-1, 0),
+            HexDirection::SouthWest => Hex::new(-1, 1),
+            HexDirection::SouthEast => Hex::new(0, 1),
+        }
+    }
+}
+
+/// Accepts `e`, `ne`, `nw`, `w`, `sw` and `se`, in any case
+impl FromStr for HexDirection {
+    type Err = ParseDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "e" => Ok(HexDirection::East),
+            "ne" => Ok(HexDirection::NorthEast),
+            "nw" => Ok(HexDirection::NorthWest),
+            "w" => Ok(HexDirection::West),
+            "sw" => Ok(HexDirection::SouthWest),
+            "se" => Ok(HexDirection::SouthEast),
+            _ => Err(ParseDirectionError),
+        }
+    }
+}
+
+#[test]
+fn test_add() {
+    let a = Cartesian::new(1, 1);
+    let b = Cartesian::new(2, 2);
+    let c = Cartesian::new(3, 3);
+    let mut d = Cartesian::new(2, 2);
+
+    assert_eq!(&a + &b, c);
+    d += &a;
+    assert_eq!(&d, &c);
+    assert_eq!(a + b, c);
+}
+
+#[test]
+fn test_sub() {
+    let a = Cartesian::new(1, 1);
+    let b = Cartesian::new(3, 2);
+    let c = Cartesian::new(2, 1);
+    let mut d = Cartesian::new(3, 2);
+
+    let (b_ref, a_ref) = (&b, &a);
+    assert_eq!(b_ref - a_ref, c);
+    d -= &a;
+    assert_eq!(&d, &c);
+    assert_eq!(b - a, c);
+    assert_eq!(-c, Cartesian::new(-2, -1));
+}
+
+#[test]
+fn test_ops() {
+    let mut a = Cartesian::new(2, 2);
+    let b = Cartesian::new(10, 10);
+
+    assert_eq!(&a * 5, b);
+    a *= 5;
+    assert_eq!(&a, &b);
+    assert_eq!(Cartesian::new(1, 0) * 3, Cartesian::new(3, 0));
+}
+
+#[test]
+fn test_rotate() {
+    let a = Cartesian::new(1, 0);
+    assert_eq!(a.rotate_cw(), Cartesian::new(0, -1));
+    assert_eq!(a.rotate_ccw(), Cartesian::new(0, 1));
+    assert_eq!(a.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), a);
+    assert_eq!(a.rotate_ccw().rotate_ccw().rotate_ccw().rotate_ccw(), a);
+
+    let pivot = Cartesian::new(1, 1);
+    assert_eq!(
+        Cartesian::new(2, 1).rotate_around(&pivot, 1),
+        Cartesian::new(1, 0)
+    );
+    assert_eq!(
+        Cartesian::new(2, 1).rotate_around(&pivot, 3),
+        Cartesian::new(1, 2)
+    );
+    assert_eq!(
+        Cartesian::new(2, 1).rotate_around(&pivot, 4),
+        Cartesian::new(2, 1)
+    );
+}
+
+#[test]
+fn test_distances() {
+    let a = Cartesian::new(1, -1);
+    let b = Cartesian::new(4, 3);
+    assert_eq!(a.manhattan_distance(&b), 7);
+    assert_eq!(a.chebyshev_distance(&b), 4);
+    assert_eq!(a.euclidean_distance(&b), 5.0);
+}
+
+#[test]
+fn test_direction() {
+    let pos = Cartesian::new(2, 2);
+    assert_eq!(
+        pos + "^".parse::<Direction>().unwrap().delta(),
+        Cartesian::new(2, 1)
+    );
+    assert_eq!(
+        pos + Direction::try_from('L').unwrap().delta(),
+        Cartesian::new(1, 2)
+    );
+    assert_eq!("x".parse::<Direction>(), Err(ParseDirectionError));
+    assert_eq!("UD".parse::<Direction>(), Err(ParseDirectionError));
+
+    for dir in Direction::all() {
+        assert_eq!(dir.turn_right().turn_left(), dir);
+        assert_eq!(dir.turn_right().turn_right(), dir.opposite());
+        assert_eq!(dir.delta() + dir.opposite().delta(), Cartesian::new(0, 0));
+    }
+    assert_eq!(
+        Direction::all()
+            .map(|d| pos + d.delta())
+            .collect::<Vec<_>>(),
+        vec![
+            Cartesian::new(2, 1),
+            Cartesian::new(3, 2),
+            Cartesian::new(2, 3),
+            Cartesian::new(1, 2),
+        ]
+    );
+}
+
+#[test]
+fn test_lattice_points() {
+    let a = Cartesian::new(0, 0);
+    assert_eq!(
+        a.lattice_points_between(&Cartesian::new(6, -4)),
+        vec![Cartesian::new(3, -2)]
+    );
+    assert_eq!(
+        a.lattice_points_on(&Cartesian::new(6, -4)),
+        vec![a, Cartesian::new(3, -2), Cartesian::new(6, -4)]
+    );
+    assert_eq!(
+        a.lattice_points_between(&Cartesian::new(0, 3)),
+        vec![Cartesian::new(0, 1), Cartesian::new(0, 2)]
+    );
+    assert!(a.lattice_points_between(&Cartesian::new(3, 2)).is_empty());
+    assert!(a.lattice_points_between(&a).is_empty());
+    assert_eq!(a.lattice_points_on(&a), vec![a]);
+}
+
+#[test]
+fn test_manhattan_disk() {
+    let center = Cartesian::new(3, -1);
+    let disk = center.manhattan_disk(2).collect::<Vec<_>>();
+    assert_eq!(disk.len(), 13);
+    assert!(disk.iter().all(|p| p.manhattan_distance(&center) <= 2));
+    assert_eq!(disk.first(), Some(&Cartesian::new(3, -3)));
+    assert_eq!(center.manhattan_disk(0).collect::<Vec<_>>(), vec![center]);
+
+    let ring = center.manhattan_ring(3).collect::<HashSet<_>>();
+    assert_eq!(ring.len(), 12);
+    assert!(ring.iter().all(|p| p.manhattan_distance(&center) == 3));
+    assert_eq!(center.manhattan_ring(0).collect::<Vec<_>>(), vec![center]);
+}
+
+#[test]
+fn test_line_to() {
+    let origin = Cartesian::new(0, 0);
+    assert_eq!(origin.line_to(&origin), vec![origin]);
+    assert_eq!(
+        origin.line_to(&Cartesian::new(-2, 2)),
+        vec![origin, Cartesian::new(-1, 1), Cartesian::new(-2, 2)]
+    );
+    assert_eq!(
+        origin.line_to(&Cartesian::new(1, -3)),
+        vec![
+            origin,
+            Cartesian::new(0, -1),
+            Cartesian::new(1, -2),
+            Cartesian::new(1, -3),
+        ]
+    );
+    assert_eq!(
+        Cartesian::new(2, 5).line_to(&Cartesian::new(0, 5)),
+        vec![
+            Cartesian::new(2, 5),
+            Cartesian::new(1, 5),
+            Cartesian::new(0, 5)
+        ]
+    );
+}
+
+#[test]
+fn test_from_str() {
+    assert_eq!("(3, -2)".parse::<Cartesian>(), Ok(Cartesian::new(3, -2)));
+    assert_eq!("4,5".parse::<Cartesian>(), Ok(Cartesian::new(4, 5)));
+    assert_eq!(
+        "".parse::<Cartesian>(),
+        Err(ParseCartesianError::MissingComponent)
+    );
+    assert_eq!(
+        "1".parse::<Cartesian>(),
+        Err(ParseCartesianError::MissingComponent)
+    );
+    assert_eq!(
+        "1,2,3".parse::<Cartesian>(),
+        Err(ParseCartesianError::TooManyComponents)
+    );
+    match "a,b".parse::<Cartesian>() {
+        Err(ParseCartesianError::Int(_)) => {}
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn test_rect_iter() {
+    let points = Cartesian::rect_iter(&Cartesian::new(-1, 0), &Cartesian::new(0, 1));
+    assert_eq!(
+        points.collect::<Vec<_>>(),
+        vec![
+            Cartesian::new(-1, 0),
+            Cartesian::new(0, 0),
+            Cartesian::new(-1, 1),
+            Cartesian::new(0, 1),
+        ]
+    );
+    assert_eq!(
+        Cartesian::rect_iter(&Cartesian::new(1, 0), &Cartesian::new(0, 1)).count(),
+        0
+    );
+}
+
+#[test]
+fn test_bounding_box() {
+    assert_eq!(bounding_box::<i32>(&[]), None);
+    assert_eq!(
+        bounding_box(&[
+            Cartesian::new(2, -1),
+            Cartesian::new(-3, 4),
+            Cartesian::new(0, 7),
+        ]),
+        Some((Cartesian::new(-3, -1), Cartesian::new(2, 7)))
+    );
+}
+
+#[test]
+fn test_display() {
+    let c = "(3, -2)".parse::<Cartesian>().unwrap();
+    assert_eq!(c.to_string(), "(3, -2)");
+    assert_eq!(c.to_string().parse::<Cartesian>(), Ok(c));
+}
+
+#[test]
+fn test_convex_hull() {
+    let square = [
+        Cartesian::new(0, 0),
+        Cartesian::new(2, 0),
+        Cartesian::new(1, 1),
+        Cartesian::new(2, 2),
+        Cartesian::new(0, 2),
+        Cartesian::new(1, 0),
+    ];
+    assert_eq!(
+        convex_hull(&square),
+        vec![
+            Cartesian::new(0, 0),
+            Cartesian::new(2, 0),
+            Cartesian::new(2, 2),
+            Cartesian::new(0, 2),
+        ]
+    );
+
+    let line = [
+        Cartesian::new(1, 1),
+        Cartesian::new(3, 3),
+        Cartesian::new(2, 2),
+    ];
+    assert_eq!(
+        convex_hull(&line),
+        vec![Cartesian::new(1, 1), Cartesian::new(3, 3)]
+    );
+    assert_eq!(
+        convex_hull(&[Cartesian::new(1, 1), Cartesian::new(1, 1)]),
+        vec![Cartesian::new(1, 1), Cartesian::new(1, 1)]
+    );
+    assert_eq!(
+        convex_hull(&[Cartesian::new(4, 4); 3]),
+        vec![Cartesian::new(4, 4)]
+    );
+}
+
+#[test]
+fn test_polygon_area() {
+    let square = [
+        Cartesian::new(0, 0),
+        Cartesian::new(1, 0),
+        Cartesian::new(1, 1),
+        Cartesian::new(0, 1),
+    ];
+    assert_eq!(polygon_area(&square), 1.0);
+    assert_eq!(lattice_points_inside(&square), 0);
+
+    let triangle = [
+        Cartesian::new(0, 0),
+        Cartesian::new(0, 4),
+        Cartesian::new(4, 0),
+    ];
+    assert_eq!(polygon_area(&triangle), 8.0);
+    assert_eq!(lattice_points_inside(&triangle), 3);
+
+    let square = [
+        Cartesian::new(0, 0),
+        Cartesian::new(0, 3),
+        Cartesian::new(3, 3),
+        Cartesian::new(3, 0),
+    ];
+    assert_eq!(polygon_area(&square), 9.0);
+    assert_eq!(lattice_points_inside(&square), 4);
+
+    /* Degenerate polygons enclose nothing */
+    assert_eq!(lattice_points_inside::<i32>(&[]), 0);
+    assert_eq!(lattice_points_inside(&[Cartesian::new(2, 3)]), 0);
+    assert_eq!(
+        lattice_points_inside(&[Cartesian::new(0, 0), Cartesian::new(2, 0)]),
+        0
+    );
+    let collinear = [
+        Cartesian::new(0, 0),
+        Cartesian::new(1, 1),
+        Cartesian::new(3, 3),
+    ];
+    assert_eq!(polygon_area(&collinear), 0.0);
+    assert_eq!(lattice_points_inside(&collinear), 0);
+}
+
+#[test]
+fn test_point_in_polygon() {
+    /* A U shape, open towards positive y */
+    let polygon = [
+        Cartesian::new(0, 0),
+        Cartesian::new(6, 0),
+        Cartesian::new(6, 6),
+        Cartesian::new(4, 6),
+        Cartesian::new(4, 2),
+        Cartesian::new(2, 2),
+        Cartesian::new(2, 6),
+        Cartesian::new(0, 6),
+    ];
+    assert!(point_in_polygon(&Cartesian::new(1, 1), &polygon));
+    assert!(point_in_polygon(&Cartesian::new(1, 4), &polygon));
+    assert!(point_in_polygon(&Cartesian::new(5, 4), &polygon));
+    assert!(!point_in_polygon(&Cartesian::new(3, 4), &polygon));
+    assert!(!point_in_polygon(&Cartesian::new(3, 3), &polygon));
+    assert!(!point_in_polygon(&Cartesian::new(7, 0), &polygon));
+    assert!(!point_in_polygon(&Cartesian::new(-1, 2), &polygon));
+
+    /* Boundary */
+    assert!(point_in_polygon(&Cartesian::new(3, 2), &polygon));
+    assert!(point_in_polygon(&Cartesian::new(4, 6), &polygon));
+    assert!(point_in_polygon(&Cartesian::new(0, 3), &polygon));
+}
+
+#[test]
+fn test_rect() {
+    let a = Rect::new(Cartesian::new(3, 5), Cartesian::new(1, 2));
+    assert_eq!(a.min, Cartesian::new(1, 2));
+    assert_eq!(a.area(), 12);
+    assert!(a.contains(&Cartesian::new(3, 2)));
+    assert!(!a.contains(&Cartesian::new(4, 2)));
+    assert_eq!(a.iter().count(), a.area());
+
+    let b = Rect::new(Cartesian::new(2, 4), Cartesian::new(6, 9));
+    assert_eq!(
+        a.intersection(&b),
+        Some(Rect::new(Cartesian::new(2, 4), Cartesian::new(3, 5)))
+    );
+
+    /* Touching edges share cells, adjacent rectangles don't */
+    let touching = Rect::new(Cartesian::new(3, 0), Cartesian::new(5, 2));
+    assert_eq!(a.intersection(&touching).map(|r| r.area()), Some(1));
+    let adjacent = Rect::new(Cartesian::new(4, 2), Cartesian::new(5, 5));
+    assert_eq!(a.intersection(&adjacent), None);
+}
+
+#[test]
+fn test_centroid() {
+    let points = [
+        Cartesian::new(0, 0),
+        Cartesian::new(4, 0),
+        Cartesian::new(4, 2),
+        Cartesian::new(0, 2),
+    ];
+    assert_eq!(centroid(&points), Some((2.0, 1.0)));
+    assert_eq!(centroid::<i32>(&[]), None);
+    assert_eq!(geometric_median::<i32>(&[]), None);
+
+    /* The median isn't pulled towards the outlier like the mean is */
+    let points = [
+        Cartesian::new(0, 0),
+        Cartesian::new(1, 0),
+        Cartesian::new(0, 1),
+        Cartesian::new(1, 1),
+        Cartesian::new(100, 100),
+    ];
+    let (x, y) = geometric_median(&points).unwrap();
+    assert!(x < 1.0 && y < 1.0);
+    let (x, y) = geometric_median(&[Cartesian::new(3, 3); 3]).unwrap();
+    assert!((x - 3.0).abs() < 1e-9 && (y - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_point_set() {
+    let set = PointSet::new(vec![
+        Cartesian::new(0, 0),
+        Cartesian::new(5, 5),
+        Cartesian::new(-3, 4),
+        Cartesian::new(4, 6),
+    ]);
+    assert_eq!(
+        set.nearest(&Cartesian::new(4, 4)),
+        Some((&Cartesian::new(5, 5), 2))
+    );
+    assert_eq!(
+        set.nearest(&Cartesian::new(-1, 2)),
+        Some((&Cartesian::new(0, 0), 3))
+    );
+    assert_eq!(
+        set.nearest(&Cartesian::new(5, 6)),
+        Some((&Cartesian::new(5, 5), 1))
+    );
+    assert_eq!(
+        PointSet::<i32>::new(vec![]).nearest(&Cartesian::new(0, 0)),
+        None
+    );
+}
+
+#[test]
+fn test_path_to_string() {
+    let path = vec![
+        Cartesian::new(0, 0),
+        Cartesian::new(1, 0),
+        Cartesian::new(1, -1),
+    ];
+    let s = path_to_string(&path);
+    assert_eq!(s, "(0, 0)->(1, 0)->(1, -1)");
+    assert_eq!(parse_path(&s), Ok(path));
+    assert_eq!(parse_path::<i32>("(0,0) -> (2,3)").unwrap().len(), 2);
+
+    assert_eq!(path_to_string::<i32>(&[]), "");
+    assert_eq!(parse_path::<i32>(""), Ok(vec![]));
+    assert_eq!(
+        parse_path::<i32>("(0, 0)->(1)"),
+        Err(ParseCartesianError::MissingComponent)
+    );
+}
+
+#[test]
+fn test_voronoi_regions() {
+    let sources = [Cartesian::new(0, 0), Cartesian::new(4, 0)];
+    let regions = voronoi_regions(&sources, (Cartesian::new(0, 0), Cartesian::new(4, 1)));
+    assert_eq!(regions.len(), 10);
+    assert_eq!(regions[&Cartesian::new(1, 1)], Some(0));
+    assert_eq!(regions[&Cartesian::new(3, 0)], Some(1));
+    /* The middle column is equally far from both */
+    assert_eq!(regions[&Cartesian::new(2, 0)], None);
+    assert_eq!(regions[&Cartesian::new(2, 1)], None);
+
+    let sizes = region_sizes(&regions);
+    assert_eq!(sizes[&0], 4);
+    assert_eq!(sizes[&1], 4);
+
+    assert!(
+        voronoi_regions(&[], (Cartesian::new(0, 0), Cartesian::new(1, 1)))
+            .values()
+            .all(|owner| owner.is_none())
+    );
+}
+
+#[test]
+fn test_winding_number() {
+    let square = [
+        Cartesian::new(0, 0),
+        Cartesian::new(4, 0),
+        Cartesian::new(4, 4),
+        Cartesian::new(0, 4),
+    ];
+    assert_eq!(winding_number(&Cartesian::new(2, 2), &square), 1);
+    let reversed = square.iter().rev().cloned().collect::<Vec<_>>();
+    assert_eq!(winding_number(&Cartesian::new(2, 2), &reversed), -1);
+    assert_eq!(winding_number(&Cartesian::new(5, 2), &square), 0);
+    /* On the loop isn't enclosed */
+    assert!(!is_enclosed(&Cartesian::new(4, 2), &square));
+    assert!(!is_enclosed(&Cartesian::new(0, 0), &square));
+
+    /* Going around twice winds twice, where even-odd says outside */
+    let twice = square
+        .iter()
+        .chain(square.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(winding_number(&Cartesian::new(1, 3), &twice), 2);
+    assert!(is_enclosed(&Cartesian::new(1, 3), &twice));
+
+    /* Cells strictly inside a traced loop */
+    let enclosed = Cartesian::rect_iter(&Cartesian::new(0, 0), &Cartesian::new(4, 4))
+        .filter(|p| is_enclosed(p, &square))
+        .count();
+    assert_eq!(enclosed, lattice_points_inside(&square));
+}
+
+#[test]
+fn test_neigh8_clockwise_from() {
+    let c = Cartesian::new(5, 5);
+    let points = c.neigh8_clockwise_from(Direction::North);
+    assert_eq!(
+        points.iter().map(|p| *p - c).collect::<Vec<_>>(),
+        vec![
+            Cartesian::new(0, -1),
+            Cartesian::new(1, -1),
+            Cartesian::new(1, 0),
+            Cartesian::new(1, 1),
+            Cartesian::new(0, 1),
+            Cartesian::new(-1, 1),
+            Cartesian::new(-1, 0),
+            Cartesian::new(-1, -1),
+        ]
+    );
+    let from_west = c.neigh8_clockwise_from(Direction::West);
+    assert_eq!(from_west[0], c + Direction::West.delta());
+    assert_eq!(from_west[2], c + Direction::North.delta());
+    assert_eq!(from_west[7], Cartesian::new(4, 6));
+
+    /* The same points as neigh8, only ordered */
+    let mut sorted = points.clone();
+    sorted.sort();
+    let mut neigh8 = c.neigh8();
+    neigh8.sort();
+    assert_eq!(sorted, neigh8);
+    assert_eq!(
+        c.neigh4(),
+        vec![
+            c + Direction::West.delta(),
+            c + Direction::South.delta(),
+            c + Direction::East.delta(),
+            c + Direction::North.delta(),
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let c = Cartesian::new(1, 2);
+    let json = serde_json::to_string(&c).unwrap();
+    assert_eq!(json, r#"{"x":1,"y":2}"#);
+    assert_eq!(serde_json::from_str::<Cartesian>(&json).unwrap(), c);
+}
+
+#[test]
+fn test_render() {
+    let points = [
+        Cartesian::new(1, 1),
+        Cartesian::new(2, 2),
+        Cartesian::new(3, 1),
+    ]
+    .iter()
+    .cloned()
+    .collect::<HashSet<_>>();
+    assert_eq!(render(&points, '#', '.'), "#.#\n.#.");
+    assert_eq!(render::<i32>(&HashSet::new(), '#', '.'), "");
+}
+
+#[test]
+fn test_spiral() {
+    let start = Cartesian::spiral().take(10).collect::<Vec<Cartesian>>();
+    assert_eq!(
+        start,
+        vec![
+            Cartesian::new(0, 0),
+            Cartesian::new(1, 0),
+            Cartesian::new(1, 1),
+            Cartesian::new(0, 1),
+            Cartesian::new(-1, 1),
+            Cartesian::new(-1, 0),
+            Cartesian::new(-1, -1),
+            Cartesian::new(0, -1),
+            Cartesian::new(1, -1),
+            Cartesian::new(2, -1),
+        ]
+    );
+    /* Square number 1024 in the classic numbering, starting from 1 */
+    assert_eq!(
+        Cartesian::<i32>::spiral().nth(1023),
+        Some(Cartesian::new(-15, 16))
+    );
+    assert_eq!(
+        Cartesian::<i32>::spiral()
+            .take(25)
+            .collect::<HashSet<_>>()
+            .len(),
+        25
+    );
+}
+
+#[test]
+fn test_lerp() {
+    let a = Cartesian::new(-2, 4);
+    let b = Cartesian::new(3, -1);
+    assert_eq!(a.lerp(&b, 0.0), (-2.0, 4.0));
+    assert_eq!(a.lerp(&b, 1.0), (3.0, -1.0));
+    assert_eq!(a.lerp(&b, 0.5), (0.5, 1.5));
+    assert_eq!(a.lerp_round(&b, 0.0), a);
+    assert_eq!(a.lerp_round(&b, 1.0), b);
+    assert_eq!(a.lerp_round(&b, 0.5), Cartesian::new(1, 2));
+    assert_eq!(a.lerp_round(&b, 0.2), Cartesian::new(-1, 3));
+}
+
+#[test]
+fn test_reflect() {
+    let c = Cartesian::new(2, 3);
+    assert_eq!(c.reflect_x(), Cartesian::new(2, -3));
+    assert_eq!(c.reflect_y(), Cartesian::new(-2, 3));
+    assert_eq!(
+        c.reflect_over(&Cartesian::new(0, 0)),
+        Cartesian::new(-2, -3)
+    );
+    assert_eq!(c.reflect_over(&Cartesian::new(1, 1)), Cartesian::new(0, -1));
+    assert_eq!(
+        c.reflect_over(&Cartesian::new(1, 1)),
+        c.rotate_around(&Cartesian::new(1, 1), 2)
+    );
+}
+
+#[test]
+fn test_overflowing_add() {
+    let a = Cartesian::new(i32::MAX - 1, i32::MIN + 1);
+    assert_eq!(
+        a.checked_add(&Cartesian::new(1, -1)),
+        Some(Cartesian::new(i32::MAX, i32::MIN))
+    );
+    assert_eq!(a.checked_add(&Cartesian::new(2, 0)), None);
+    assert_eq!(a.checked_add(&Cartesian::new(0, -2)), None);
+    assert_eq!(
+        a.saturating_add(&Cartesian::new(5, -5)),
+        Cartesian::new(i32::MAX, i32::MIN)
+    );
+    assert_eq!(
+        a.saturating_add(&Cartesian::new(-1, 1)),
+        Cartesian::new(i32::MAX - 2, i32::MIN + 2)
+    );
+}
+
+#[test]
+fn test_components() {
+    let a = Cartesian::new(-3, 2);
+    let b = Cartesian::new(1, -5);
+    assert_eq!(a.abs(), Cartesian::new(3, 2));
+    assert_eq!(b.abs(), Cartesian::new(1, 5));
+    assert_eq!(a.min_components(&b), Cartesian::new(-3, -5));
+    assert_eq!(a.max_components(&b), Cartesian::new(1, 2));
+}
+
+#[test]
+fn test_dot_cross() {
+    let a = Cartesian::new(2, 1);
+    let b = Cartesian::new(-1, 3);
+    assert_eq!(a.dot(&b), 1);
+    assert_eq!(a.cross(&b), 7);
+    assert_eq!(b.cross(&a), -7);
+    assert_eq!(a.cross(&(a * 3)), 0);
+    assert_eq!(a.dot(&a.rotate_cw()), 0);
+}
+
+#[test]
+fn test_signum() {
+    assert_eq!(Cartesian::new(-7, 3).signum(), Cartesian::new(-1, 1));
+    assert_eq!(Cartesian::new(0, -2).signum(), Cartesian::new(0, -1));
+    assert_eq!(Cartesian::new(0, 0).signum(), Cartesian::new(0, 0));
+}
+
+#[test]
+fn test_neigh_where() {
+    let c = Cartesian::new(0, 0);
+    let open = c.neigh4_where(|n| n.x >= 0 && n.y >= 0).collect::<Vec<_>>();
+    assert_eq!(open, vec![Cartesian::new(0, 1), Cartesian::new(1, 0)]);
+    assert_eq!(c.neigh8_where(|n| n.x != 0).count(), 6);
+}
+
+#[test]
+fn test_neigh_wrapped() {
+    let mut neighbors = Cartesian::new(0, 0).neigh4_wrapped(5, 3);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![
+            Cartesian::new(1, 0),
+            Cartesian::new(4, 0),
+            Cartesian::new(0, 1),
+            Cartesian::new(0, 2),
+        ]
+    );
+    let neighbors = Cartesian::new(4, 2).neigh8_wrapped(5, 3);
+    assert_eq!(neighbors.len(), 8);
+    assert!(neighbors.contains(&Cartesian::new(0, 0)));
+    assert!(neighbors.contains(&Cartesian::new(3, 1)));
+}
+
+#[test]
+fn test_knight_moves() {
+    let mut moves = Cartesian::new(0, 0).knight_moves();
+    moves.sort();
+    let mut expected = vec![
+        Cartesian::new(1, 2),
+        Cartesian::new(2, 1),
+        Cartesian::new(2, -1),
+        Cartesian::new(1, -2),
+        Cartesian::new(-1, -2),
+        Cartesian::new(-2, -1),
+        Cartesian::new(-2, 1),
+        Cartesian::new(-1, 2),
+    ];
+    expected.sort();
+    assert_eq!(moves, expected);
+    assert!(Cartesian::new(5, 5)
+        .knight_moves()
+        .iter()
+        .all(|m| m.manhattan_distance(&Cartesian::new(5, 5)) == 3));
+}
+
+#[test]
+fn test_parse_grid() {
+    let grid = parse_grid("#.\n..#\n\n");
+    assert_eq!(grid.len(), 5);
+    assert_eq!(grid[&Cartesian::new(0, 0)], '#');
+    assert_eq!(grid[&Cartesian::new(2, 1)], '#');
+    assert_eq!(grid.get(&Cartesian::new(2, 0)), None);
+    assert!(parse_grid("").is_empty());
+}
+
+#[test]
+fn test_turtle() {
+    let mut turtle = Turtle::new(Cartesian::new(0, 0), Direction::North);
+    let visited = turtle.run("R2, L1,R1\nF1").unwrap();
+    assert_eq!(
+        visited,
+        vec![
+            Cartesian::new(0, 0),
+            Cartesian::new(1, 0),
+            Cartesian::new(2, 0),
+            Cartesian::new(2, -1),
+            Cartesian::new(3, -1),
+            Cartesian::new(4, -1),
+        ]
+    );
+    assert_eq!(turtle.position, Cartesian::new(4, -1));
+    assert_eq!(turtle.facing, Direction::East);
+    turtle.turn_left();
+    turtle.turn_left();
+    assert_eq!(
+        turtle.forward(2),
+        vec![Cartesian::new(3, -1), Cartesian::new(2, -1)]
+    );
+
+    assert_eq!(
+        turtle.run("R1, X2"),
+        Err(ParseTurtleError::UnknownCommand('X'))
+    );
+    match turtle.run("L1, Lx") {
+        Err(ParseTurtleError::Steps(_)) => {}
+        r => panic!("Unexpected result {:?}", r),
+    }
+    assert!(turtle.run("F").is_err());
+}
+
+#[test]
+fn test_first_revisit() {
+    let mut turtle = Turtle::new(Cartesian::new(0, 0), Direction::North);
+    let visited = turtle.run("R8, R4, R4, R8").unwrap();
+    assert_eq!(
+        first_revisit(visited.into_iter()),
+        Some(Cartesian::new(4, 0))
+    );
+    assert_eq!(
+        first_revisit(
+            Cartesian::new(0, 0)
+                .line_to(&Cartesian::new(5, 5))
+                .into_iter()
+        ),
+        None
+    );
+
+    /* Only consumes what it needs of an endless path */
+    let circle = Cartesian::new(0, 0).neigh4().into_iter().cycle();
+    assert_eq!(first_revisit(circle), Some(Cartesian::new(-1, 0)));
+}
+
+#[test]
+fn test_cartesian3() {
+    let a = "1,2,3".parse::<Cartesian3>().unwrap();
+    let b = Cartesian3::new(-1, 0, 5);
+    assert_eq!(a, Cartesian3::new(1, 2, 3));
+    assert_eq!(a + b, Cartesian3::new(0, 2, 8));
+    assert_eq!(a - b, Cartesian3::new(2, 2, -2));
+    assert_eq!(a.manhattan_distance(&b), 6);
+    assert_eq!(a.neigh6().len(), 6);
+    assert_eq!(a.neigh26().len(), 26);
+    assert!(a.neigh26().iter().all(|n| n.manhattan_distance(&a) <= 3));
+    assert_eq!(
+        "1,2".parse::<Cartesian3>(),
+        Err(ParseCartesianError::MissingComponent)
+    );
+}
+
+#[test]
+fn test_generic() {
+    let a = Cartesian::<i64>::new(3_000_000_000, -3_000_000_000);
+    let b = Cartesian::<i64>::new(-3_000_000_000, 3_000_000_000);
+    assert_eq!(a.manhattan_distance(&b), 12_000_000_000);
+    assert_eq!(a + b, Cartesian::new(0, 0));
+    assert_eq!(&a * 2, Cartesian::new(6_000_000_000, -6_000_000_000));
+    assert!(a
+        .neigh4()
+        .contains(&Cartesian::new(3_000_000_001, -3_000_000_000)));
+    assert_eq!(a.neigh8().len(), 8);
+    assert_eq!(
+        "(5000000000, 1)".parse::<Cartesian<i64>>(),
+        Ok(Cartesian::new(5_000_000_000, 1))
+    );
+}
+
+#[test]
+fn test_ord() {
+    let mut points = vec![
+        Cartesian::new(2, 1),
+        Cartesian::new(0, 2),
+        Cartesian::new(1, 1),
+        Cartesian::new(5, 0),
+    ];
+    points.sort();
+    assert_eq!(
+        points,
+        vec![
+            Cartesian::new(5, 0),
+            Cartesian::new(1, 1),
+            Cartesian::new(2, 1),
+            Cartesian::new(0, 2),
+        ]
+    );
+}
+
+#[test]
+fn test_from_tuple() {
+    let c: Cartesian = (3, 4).into();
+    assert_eq!(c, Cartesian::new(3, 4));
+    assert_eq!(Cartesian::from([-1, 2]), Cartesian::new(-1, 2));
+    let (x, y) = c.into();
+    assert_eq!((x, y), (3, 4));
+}
+
+#[test]
+fn test_hex() {
+    let origin = Hex::new(0, 0);
+    let neighbors = origin.neighbors();
+    assert_eq!(neighbors.len(), 6);
+    assert!(neighbors.iter().all(|n| n.distance(&origin) == 1));
+
+    let path = ["ne", "e", "SE", " sw "]
+        .iter()
+        .map(|d| d.parse::<HexDirection>().unwrap())
+        .fold(origin, |h, d| h + d.delta());
+    assert_eq!(path, Hex::new(1, 1));
+    assert_eq!(path.distance(&origin), 2);
+    assert_eq!(Hex::new(3, -1).distance(&Hex::new(-1, 2)), 4);
+    assert_eq!("n".parse::<HexDirection>(), Err(ParseDirectionError));
+
+    /* Opposite directions cancel */
+    let deltas = HexDirection::all().map(|d| d.delta()).collect::<Vec<_>>();
+    for i in 0..3 {
+        assert_eq!(deltas[i] + deltas[i + 3], origin);
+    }
+    assert_eq!(Hex::new(1, 1).to_cartesian(), Cartesian::new(3, 1));
 }
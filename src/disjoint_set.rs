@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Disjoint-set, or union-find, structure keeping track of which elements
+/// belong to the same set. Uses path compression and union by rank.
+pub struct DisjointSet<T>
+where
+    T: Hash + Eq + Clone,
+{
+    index: HashMap<T, usize>,
+    elements: Vec<T>,
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+    sets: usize,
+}
+
+impl<T> DisjointSet<T>
+where
+    T: Hash + Eq + Clone,
+{
+    pub fn new() -> DisjointSet<T> {
+        DisjointSet {
+            index: HashMap::new(),
+            elements: Vec::new(),
+            parent: Vec::new(),
+            rank: Vec::new(),
+            sets: 0,
+        }
+    }
+
+    /// Add `x` as a set of its own. Returns false if `x` was already present
+    pub fn make_set(&mut self, x: T) -> bool {
+        if self.index.contains_key(&x) {
+            return false;
+        }
+        let i = self.elements.len();
+        self.index.insert(x.clone(), i);
+        self.elements.push(x);
+        self.parent.push(i);
+        self.rank.push(0);
+        self.sets += 1;
+        true
+    }
+
+    fn find_index(&mut self, mut i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        /* Compress the path, pointing everything along it directly to root */
+        while self.parent[i] != root {
+            let next = self.parent[i];
+            self.parent[i] = root;
+            i = next;
+        }
+        root
+    }
+
+    /// Find the representative of the set containing `x`, if `x` is present
+    pub fn find(&mut self, x: &T) -> Option<&T> {
+        let i = *self.index.get(x)?;
+        let root = self.find_index(i);
+        Some(&self.elements[root])
+    }
+
+    /// Merge the sets containing `a` and `b`, adding them first if not present.
+    /// Returns false if they were already in the same set.
+    pub fn union(&mut self, a: &T, b: &T) -> bool {
+        self.make_set(a.clone());
+        self.make_set(b.clone());
+        let a = self.find_index(self.index[a]);
+        let b = self.find_index(self.index[b]);
+        if a == b {
+            return false;
+        }
+
+        if self.rank[a] < self.rank[b] {
+            self.parent[a] = b;
+        } else {
+            self.parent[b] = a;
+            if self.rank[a] == self.rank[b] {
+                self.rank[a] += 1;
+            }
+        }
+        self.sets -= 1;
+        true
+    }
+
+    /// Check if `a` and `b` are present and belong to the same set
+    pub fn same_set(&mut self, a: &T, b: &T) -> bool {
+        match (self.index.get(a).cloned(), self.index.get(b).cloned()) {
+            (Some(a), Some(b)) => self.find_index(a) == self.find_index(b),
+            _ => false,
+        }
+    }
+
+    /// Number of disjoint sets
+    pub fn count_sets(&self) -> usize {
+        self.sets
+    }
+
+    /// Iterate over the representative of each set
+    pub fn representatives(&self) -> impl Iterator<Item = &T> {
+        self.elements
+            .iter()
+            .enumerate()
+            .filter(move |&(i, _)| self.parent[i] == i)
+            .map(|(_, x)| x)
+    }
+}
+
+impl<T> Default for DisjointSet<T>
+where
+    T: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        DisjointSet::new()
+    }
+}
+
+#[test]
+fn test_disjoint_set() {
+    let mut set = DisjointSet::new();
+    for i in 0..6 {
+        assert!(set.make_set(i));
+    }
+    assert!(!set.make_set(0));
+    assert_eq!(set.count_sets(), 6);
+
+    assert!(set.union(&0, &1));
+    assert!(set.union(&2, &3));
+    assert!(set.union(&1, &3));
+    assert!(!set.union(&0, &2));
+    assert!(set.union(&6, &7));
+
+    assert!(set.same_set(&0, &3));
+    assert!(!set.same_set(&0, &4));
+    assert!(!set.same_set(&0, &8));
+    let root = set.find(&1).cloned();
+    assert_eq!(set.find(&2), root.as_ref());
+    assert_eq!(set.find(&8), None);
+    assert_eq!(set.count_sets(), 4);
+    assert_eq!(set.representatives().count(), 4);
+}
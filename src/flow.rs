@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+struct FlowEdge {
+    to: usize,
+    capacity: isize,
+    /// Negative on a reverse edge by as much as flows through its partner
+    flow: isize,
+}
+
+/// Directed network of edges with capacities between vertices given by index,
+/// for computing maximum flows and minimum cuts. Every edge is stored along with
+/// its residual reverse edge of capacity 0, at the neighboring index. Pushing
+/// flow along an edge adds to its flow and subtracts the same from its partner,
+/// so flow pushed along an edge can later be pushed back.
+pub struct FlowNetwork {
+    edges: Vec<FlowEdge>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl FlowNetwork {
+    /// Create a network from a list of `(from, to, capacity)` edges. The number
+    /// of vertices is given by the largest id used.
+    pub fn new(edges: &[(usize, usize, usize)]) -> FlowNetwork {
+        let mut network = FlowNetwork {
+            edges: Vec::new(),
+            adjacency: Vec::new(),
+        };
+        for &(from, to, capacity) in edges {
+            network.add_edge(from, to, capacity);
+        }
+        network
+    }
+
+    /// Add an edge from `from` to `to` with the given `capacity`
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: usize) {
+        let size = from.max(to) + 1;
+        if self.adjacency.len() < size {
+            self.adjacency.resize(size, Vec::new());
+        }
+        self.adjacency[from].push(self.edges.len());
+        self.edges.push(FlowEdge {
+            to,
+            capacity: capacity as isize,
+            flow: 0,
+        });
+        self.adjacency[to].push(self.edges.len());
+        self.edges.push(FlowEdge {
+            to: from,
+            capacity: 0,
+            flow: 0,
+        });
+    }
+
+    fn residual(&self, edge: usize) -> isize {
+        self.edges[edge].capacity - self.edges[edge].flow
+    }
+
+    /// Find the maximum flow from `source` to `sink` with the Edmonds-Karp
+    /// algorithm, i.e. repeatedly augmenting along shortest paths found with
+    /// BFS. The flow is kept in the network, so calling this again continues
+    /// from it. Vertices without any edges have no flow through them.
+    pub fn max_flow(&mut self, source: usize, sink: usize) -> usize {
+        if source >= self.adjacency.len() || sink >= self.adjacency.len() {
+            return 0;
+        }
+        let mut total = 0;
+        while let Some(came_from) = self.augmenting_path(source, sink) {
+            let mut bottleneck = isize::MAX;
+            let mut node = sink;
+            while node != source {
+                let edge = came_from[node].unwrap();
+                bottleneck = bottleneck.min(self.residual(edge));
+                node = self.edges[edge ^ 1].to;
+            }
+
+            let mut node = sink;
+            while node != source {
+                let edge = came_from[node].unwrap();
+                self.edges[edge].flow += bottleneck;
+                self.edges[edge ^ 1].flow -= bottleneck;
+                node = self.edges[edge ^ 1].to;
+            }
+            total += bottleneck as usize;
+        }
+        total
+    }
+
+    /// BFS through edges with residual capacity, returning the edge used to
+    /// reach each vertex if `sink` is reachable
+    fn augmenting_path(&self, source: usize, sink: usize) -> Option<Vec<Option<usize>>> {
+        let mut came_from = vec![None; self.adjacency.len()];
+        let mut seen = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::new();
+        seen[source] = true;
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for &edge in &self.adjacency[node] {
+                let to = self.edges[edge].to;
+                if !seen[to] && self.residual(edge) > 0 {
+                    seen[to] = true;
+                    came_from[to] = Some(edge);
+                    queue.push_back(to);
+                }
+            }
+        }
+        if seen[sink] && source != sink {
+            Some(came_from)
+        } else {
+            None
+        }
+    }
+
+    /// Vertices reachable from `source` through edges with residual capacity
+    fn residual_reachable(&self, source: usize) -> Vec<bool> {
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut stack = vec![source];
+        visited[source] = true;
+        while let Some(node) = stack.pop() {
+            for &edge in &self.adjacency[node] {
+                let to = self.edges[edge].to;
+                if !visited[to] && self.residual(edge) > 0 {
+                    visited[to] = true;
+                    stack.push(to);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Edges of a minimum cut separating `source` from the sink, as
+    /// `(from, to, capacity)`. Only meaningful after `max_flow` has been run
+    /// from the same `source`, the total capacity then equals the maximum flow.
+    /// A `source` without any edges gives an empty cut.
+    pub fn min_cut(&self, source: usize) -> Vec<(usize, usize, usize)> {
+        if source >= self.adjacency.len() {
+            return Vec::new();
+        }
+        let reachable = self.residual_reachable(source);
+        let mut cut = Vec::new();
+        for (from, edges) in self.adjacency.iter().enumerate() {
+            for &edge in edges {
+                let e = &self.edges[edge];
+                /* Forward edges are at even indices */
+                if edge % 2 == 0 && reachable[from] && !reachable[e.to] {
+                    cut.push((from, e.to, e.capacity as usize));
+                }
+            }
+        }
+        cut
+    }
+}
+
+/// Find a maximum matching in a bipartite graph given as the adjacency of each
+/// left vertex, using Kuhn's augmenting path algorithm. Returns the assignment
+/// of matched left vertices to right vertices, the number of matches being its
+/// length.
+pub fn maximum_bipartite_matching(
+    left_adjacency: &HashMap<usize, Vec<usize>>,
+) -> HashMap<usize, usize> {
+    let mut left = left_adjacency.keys().cloned().collect::<Vec<_>>();
+    left.sort();
+    let mut matched_right = HashMap::<usize, usize>::new();
+    for l in left {
+        let mut visited = HashSet::new();
+        augment(left_adjacency, l, &mut visited, &mut matched_right);
+    }
+    matched_right.into_iter().map(|(r, l)| (l, r)).collect()
+}
+
+/// Try to match `l`, possibly moving already matched left vertices to other
+/// right vertices. Returns true if the matching grew.
+fn augment(
+    left_adjacency: &HashMap<usize, Vec<usize>>,
+    l: usize,
+    visited: &mut HashSet<usize>,
+    matched_right: &mut HashMap<usize, usize>,
+) -> bool {
+    for &r in &left_adjacency[&l] {
+        if !visited.insert(r) {
+            continue;
+        }
+        let free = match matched_right.get(&r) {
+            None => true,
+            Some(&other) => augment(left_adjacency, other, visited, matched_right),
+        };
+        if free {
+            matched_right.insert(r, l);
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn test_max_flow() {
+    let mut network = FlowNetwork::new(&[
+        (0, 1, 16),
+        (0, 2, 13),
+        (1, 2, 10),
+        (2, 1, 4),
+        (1, 3, 12),
+        (3, 2, 9),
+        (2, 4, 14),
+        (4, 3, 7),
+        (3, 5, 20),
+        (4, 5, 4),
+    ]);
+    assert_eq!(network.max_flow(0, 5), 23);
+    let cut = network.min_cut(0);
+    assert_eq!(cut.iter().map(|&(_, _, c)| c).sum::<usize>(), 23);
+    assert_eq!(cut, vec![(1, 3, 12), (4, 3, 7), (4, 5, 4)]);
+
+    /* Nothing more can be pushed */
+    assert_eq!(network.max_flow(0, 5), 0);
+}
+
+#[test]
+fn test_max_flow_disconnected() {
+    let mut network = FlowNetwork::new(&[(0, 1, 5), (2, 3, 5)]);
+    assert_eq!(network.max_flow(0, 3), 0);
+    assert_eq!(network.min_cut(0), vec![]);
+
+    /* Vertices beyond any edge */
+    assert_eq!(network.max_flow(0, 9), 0);
+    assert_eq!(network.max_flow(9, 0), 0);
+    assert_eq!(network.min_cut(9), vec![]);
+}
+
+#[test]
+fn test_maximum_bipartite_matching() {
+    /* The greedy choice for 0 has to be moved for 1 to be matched */
+    let adjacency = [(0, vec![10, 11]), (1, vec![10]), (2, vec![10, 11])]
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+    let matching = maximum_bipartite_matching(&adjacency);
+    assert_eq!(matching.len(), 2);
+    assert_eq!(matching[&1], 10);
+    assert_eq!(matching[&0], 11);
+
+    let adjacency = [(0, vec![]), (1, vec![5])]
+        .iter()
+        .cloned()
+        .collect::<HashMap<_, _>>();
+    let matching = maximum_bipartite_matching(&adjacency);
+    assert_eq!(matching.into_iter().collect::<Vec<_>>(), vec![(1, 5)]);
+}
@@ -1,20 +1,55 @@
-use std::cmp::Ordering;
+use coord::Cartesian;
+use disjoint_set::DisjointSet;
+#[cfg(feature = "rand")]
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::cmp::{Ordering, Reverse};
 use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
-use std::fmt::Debug;
-use std::hash::Hash;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::usize;
 
 pub trait Vertex {
     fn neighbors(&self) -> Vec<Rc<Self>>;
     fn distance(&self, other: &Self) -> usize;
+
+    /// Cost of moving from `self` to the neighbor `other`. Defaults to unit cost
+    fn edge_cost(&self, _other: &Self) -> usize {
+        1
+    }
+
+    /// Neighbors along with the cost of moving to each, for when computing
+    /// costs separately is awkward. Every function weighting edges by unsigned
+    /// costs uses this rather than `edge_cost`, so overriding it is enough.
+    /// Defaults to `neighbors` weighted by `edge_cost`
+    fn neighbors_with_cost(&self) -> Vec<(Rc<Self>, usize)> {
+        self.neighbors()
+            .into_iter()
+            .map(|n| {
+                let cost = self.edge_cost(&n);
+                (n, cost)
+            })
+            .collect()
+    }
+
+    /// Cost of moving from `self` to the neighbor `other`, allowing negative
+    /// costs. Defaults to `edge_cost`
+    fn signed_edge_cost(&self, other: &Self) -> i64 {
+        self.edge_cost(other) as i64
+    }
 }
 
+/// Entry of an open set, ordered so that `BinaryHeap` pops the lowest score
+/// first. Ties are broken by `sequence`, popping the entry pushed first, so
+/// searches are deterministic when several paths are equally good.
 struct ScoredVertex<T>
 where
     T: Vertex + Hash + Eq,
 {
     score: usize,
+    sequence: usize,
     vertex: Rc<T>,
 }
 
@@ -23,7 +58,10 @@ where
     T: Vertex + Hash + Eq,
 {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.score.cmp(&self.score)
+        other
+            .score
+            .cmp(&self.score)
+            .then_with(|| other.sequence.cmp(&self.sequence))
     }
 }
 
@@ -41,7 +79,7 @@ where
     T: Vertex + Hash + Eq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.score == other.score
+        self.score == other.score && self.sequence == other.sequence
     }
 }
 
@@ -51,62 +89,720 @@ impl<T> ScoredVertex<T>
 where
     T: Vertex + Hash + Eq,
 {
-    fn new(vertex: Rc<T>, score: usize) -> ScoredVertex<T> {
+    /// Create an entry, where `sequence` should increase with every push
+    fn new(vertex: Rc<T>, score: usize, sequence: usize) -> ScoredVertex<T> {
         ScoredVertex {
             vertex: vertex,
             score: score,
+            sequence,
         }
     }
 }
 
-fn reconstruct_path<T>(mut goal: Rc<T>, came_from: &HashMap<Rc<T>, Rc<T>>) -> Vec<Rc<T>>
+/// Walk `came_from` back from `goal`, returning the path ordered from start to goal
+fn reconstruct_path<T>(goal: Rc<T>, came_from: &HashMap<Rc<T>, Rc<T>>) -> Vec<Rc<T>>
 where
     T: Vertex + Hash + Eq + Debug,
 {
-    let mut path = vec![goal.clone()];
-    while let Some(prev) = came_from.get(goal.as_ref()) {
-        path.push(prev.clone());
-        goal = prev.clone();
-    }
+    let mut path = PathIter::new(goal, came_from).collect::<Vec<_>>();
+    path.reverse();
     return path;
 }
 
+/// Iterator lazily walking a `came_from` map from the goal back to the start,
+/// without allocating the whole path. Iterating from the back, i.e. from the
+/// start, requires the remaining path to be walked and buffered first.
+pub struct PathIter<'a, T>
+where
+    T: 'a + Hash + Eq,
+{
+    next: Option<Rc<T>>,
+    came_from: &'a HashMap<Rc<T>, Rc<T>>,
+    buffer: VecDeque<Rc<T>>,
+}
+
+impl<'a, T> PathIter<'a, T>
+where
+    T: 'a + Hash + Eq,
+{
+    pub fn new(goal: Rc<T>, came_from: &'a HashMap<Rc<T>, Rc<T>>) -> PathIter<'a, T> {
+        PathIter {
+            next: Some(goal),
+            came_from,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    fn walk(&mut self) -> Option<Rc<T>> {
+        let current = self.next.take()?;
+        self.next = self.came_from.get(&current).cloned();
+        Some(current)
+    }
+}
+
+impl<'a, T> Iterator for PathIter<'a, T>
+where
+    T: 'a + Hash + Eq,
+{
+    type Item = Rc<T>;
+
+    fn next(&mut self) -> Option<Rc<T>> {
+        self.buffer.pop_front().or_else(|| self.walk())
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for PathIter<'a, T>
+where
+    T: 'a + Hash + Eq,
+{
+    fn next_back(&mut self) -> Option<Rc<T>> {
+        while let Some(v) = self.walk() {
+            self.buffer.push_back(v);
+        }
+        self.buffer.pop_back()
+    }
+}
+
 /// Search for paths from `start` to `goal` using BFS traversing.
-/// Return list of all found paths
+/// Return list of all simple paths, i.e. paths not visiting any vertex
+/// twice, ordered by length. Beware that the number of simple paths can
+/// grow exponentially with the size of the graph.
 pub fn bfs_search_all<T>(start: Rc<T>, goal: Rc<T>) -> Vec<Vec<Rc<T>>>
 where
     T: Vertex + Hash + Eq + Debug,
 {
-    let mut queue: VecDeque<Rc<T>> = VecDeque::new();
-    let mut prev: HashMap<Rc<T>, Rc<T>> = HashMap::new();
+    let mut queue: VecDeque<Vec<Rc<T>>> = VecDeque::new();
     let mut result = Vec::new();
 
-    queue.push_back(start.clone());
-    while queue.len() > 0 {
-        let current = queue.pop_front().unwrap();
+    queue.push_back(vec![start]);
+    while let Some(path) = queue.pop_front() {
+        let current = path.last().unwrap().clone();
         if current.distance(&goal) == 0 {
             /* Found path */
-            result.push(reconstruct_path(current.clone(), &prev));
+            result.push(path);
         } else {
-            current.neighbors().iter().for_each(|n| {
-                prev.insert(n.clone(), current.clone());
-                queue.push_back(n.clone());
-            });
+            for n in current.neighbors() {
+                /* Only extend the path with vertices not already on it */
+                if !path.contains(&n) {
+                    let mut next = path.clone();
+                    next.push(n);
+                    queue.push_back(next);
+                }
+            }
         }
     }
 
     result
 }
 
+/// Search for a shortest path from `start` to `goal` with BFS, returning it
+/// ordered from start to goal. This is the simplest search for graphs where
+/// every edge has the same cost.
+pub fn bfs_shortest_path<T>(start: Rc<T>, goal: Rc<T>) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut queue = VecDeque::new();
+    let mut visited = HashSet::<Rc<T>>::new();
+    let mut came_from = HashMap::<Rc<T>, Rc<T>>::new();
+
+    visited.insert(start.clone());
+    queue.push_back(start);
+    while let Some(current) = queue.pop_front() {
+        if current == goal {
+            return Some(reconstruct_path(current, &came_from));
+        }
+        for n in current.neighbors() {
+            if visited.insert(n.clone()) {
+                came_from.insert(n.clone(), current.clone());
+                queue.push_back(n);
+            }
+        }
+    }
+    None
+}
+
+/// Find every shortest path from `start` to `goal`, ordered from start to goal,
+/// treating every edge as having unit cost. BFS records all predecessors at
+/// the optimal distance of each vertex, and the paths are then enumerated from
+/// those. Beware that the number of shortest paths can grow exponentially, e.g.
+/// across an open grid.
+pub fn all_shortest_paths<T>(start: Rc<T>, goal: Rc<T>) -> Vec<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut distance = HashMap::<Rc<T>, usize>::new();
+    let mut predecessors = HashMap::<Rc<T>, Vec<Rc<T>>>::new();
+    let mut queue = VecDeque::new();
+
+    distance.insert(start.clone(), 0);
+    queue.push_back(start.clone());
+    while let Some(current) = queue.pop_front() {
+        let d = distance[&current];
+        match distance.get(&goal) {
+            /* All of the goal's predecessors have been expanded */
+            Some(&g) if d >= g => break,
+            _ => {}
+        }
+        for n in current.neighbors() {
+            match distance.get(&n).cloned() {
+                None => {
+                    distance.insert(n.clone(), d + 1);
+                    predecessors.insert(n.clone(), vec![current.clone()]);
+                    queue.push_back(n);
+                }
+                Some(dn) if dn == d + 1 => {
+                    let p = predecessors.get_mut(&n).unwrap();
+                    if !p.contains(&current) {
+                        p.push(current.clone());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    if !distance.contains_key(&goal) {
+        return Vec::new();
+    }
+
+    let mut paths = Vec::new();
+    let mut stack = vec![vec![goal]];
+    while let Some(path) = stack.pop() {
+        let last = path.last().unwrap().clone();
+        if last == start {
+            paths.push(path.into_iter().rev().collect());
+            continue;
+        }
+        for p in predecessors[&last].iter().rev() {
+            let mut next = path.clone();
+            next.push(p.clone());
+            stack.push(next);
+        }
+    }
+    paths
+}
+
+/// Search for the shortest path between two vertices with BFS, expanding from
+/// both `start` and `goal` until the frontiers meet. Searching backwards from
+/// `goal` follows `neighbors`, so edges are assumed to be undirected.
+pub fn bidirectional_bfs<T>(start: Rc<T>, goal: Rc<T>) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if start == goal {
+        return Some(vec![start]);
+    }
+
+    /* Predecessor towards start, and successor towards goal, of each vertex */
+    let mut prev_start = HashMap::<Rc<T>, Rc<T>>::new();
+    let mut prev_goal = HashMap::<Rc<T>, Rc<T>>::new();
+    let mut dist_start = HashMap::<Rc<T>, usize>::new();
+    let mut dist_goal = HashMap::<Rc<T>, usize>::new();
+    dist_start.insert(start.clone(), 0);
+    dist_goal.insert(goal.clone(), 0);
+    let mut frontier_start = vec![start];
+    let mut frontier_goal = vec![goal];
+
+    while !frontier_start.is_empty() && !frontier_goal.is_empty() {
+        /* Always expand the smaller frontier */
+        let meeting = if frontier_start.len() <= frontier_goal.len() {
+            let (next, meeting) = expand_layer(
+                &frontier_start,
+                &mut dist_start,
+                &mut prev_start,
+                &dist_goal,
+            );
+            frontier_start = next;
+            meeting
+        } else {
+            let (next, meeting) =
+                expand_layer(&frontier_goal, &mut dist_goal, &mut prev_goal, &dist_start);
+            frontier_goal = next;
+            meeting
+        };
+
+        if let Some(meeting) = meeting {
+            let mut path = reconstruct_path(meeting.clone(), &prev_start);
+            let mut current = meeting;
+            while let Some(next) = prev_goal.get(&current) {
+                path.push(next.clone());
+                current = next.clone();
+            }
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Expand one BFS layer, returning the next frontier and the meeting point with
+/// the other search giving the shortest total path, if the searches met.
+fn expand_layer<T>(
+    frontier: &[Rc<T>],
+    dist: &mut HashMap<Rc<T>, usize>,
+    prev: &mut HashMap<Rc<T>, Rc<T>>,
+    other_dist: &HashMap<Rc<T>, usize>,
+) -> (Vec<Rc<T>>, Option<Rc<T>>)
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut next = Vec::new();
+    let mut best: Option<(usize, Rc<T>)> = None;
+    for current in frontier {
+        let d = dist[current] + 1;
+        for n in current.neighbors() {
+            if dist.contains_key(&n) {
+                continue;
+            }
+            dist.insert(n.clone(), d);
+            prev.insert(n.clone(), current.clone());
+            if let Some(&other) = other_dist.get(&n) {
+                match best {
+                    Some((b, _)) if b <= d + other => {}
+                    _ => best = Some((d + other, n.clone())),
+                }
+            }
+            next.push(n);
+        }
+    }
+    (next, best.map(|(_, n)| n))
+}
+
+/// Calculate the unit-cost distance from `start` to every reachable vertex
+pub fn bfs_distances<T>(start: Rc<T>) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    multi_source_bfs(&[start])
+}
+
+/// The largest unit-cost distance from `start` to any vertex reachable from it
+pub fn eccentricity<T>(start: Rc<T>) -> usize
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    bfs_distances(start).values().cloned().max().unwrap_or(0)
+}
+
+/// The largest unit-cost distance between any pair of vertices reachable from
+/// `nodes`, where the second is reachable from the first. Runs a BFS from every
+/// vertex, so it takes `O(V * (V + E))` time, which gets slow on large graphs.
+pub fn diameter<T>(nodes: &[Rc<T>]) -> usize
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    multi_source_bfs(nodes)
+        .keys()
+        .map(|v| eccentricity(v.clone()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Calculate the unit-cost distance from every vertex reachable from `starts`
+/// to the closest of them, expanding from all sources at once.
+pub fn multi_source_bfs<T>(starts: &[Rc<T>]) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    multi_source_bfs_nearest(starts)
+        .into_iter()
+        .map(|(n, (d, _))| (n, d))
+        .collect()
+}
+
+/// Same as `multi_source_bfs`, but also tells which source is the closest by
+/// mapping each vertex to its distance and the index of the source in `starts`.
+/// Ties are won by the source appearing first in `starts`.
+pub fn multi_source_bfs_nearest<T>(starts: &[Rc<T>]) -> HashMap<Rc<T>, (usize, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut dist = HashMap::<Rc<T>, (usize, usize)>::new();
+    let mut queue = VecDeque::new();
+
+    for (i, start) in starts.iter().enumerate() {
+        if !dist.contains_key(start) {
+            dist.insert(start.clone(), (0, i));
+            queue.push_back(start.clone());
+        }
+    }
+    while let Some(current) = queue.pop_front() {
+        let (d, source) = dist[&current];
+        for n in current.neighbors() {
+            if !dist.contains_key(&n) {
+                dist.insert(n.clone(), (d + 1, source));
+                queue.push_back(n);
+            }
+        }
+    }
+    dist
+}
+
 /// Search for the shortest path between two Vertices.
 /// Uses `Rc`, as it is otherwise hard to know size of
-/// objects at compile time.
+/// objects at compile time. Edges are weighted by `Vertex::neighbors_with_cost`, while
+/// `Vertex::distance` is only used as the heuristic estimate.
 pub fn astar_search<T>(start: Rc<T>, goal: Rc<T>) -> Option<Vec<Rc<T>>>
 where
     T: Vertex + Hash + Eq + Debug,
 {
+    astar_search_cost(start, goal).map(|(path, _)| path)
+}
+
+/// Same as `astar_search`, but also returns the cost of the found path,
+/// i.e. the accumulated step cost to reach `goal`, not the heuristic estimate.
+pub fn astar_search_cost<T>(start: Rc<T>, goal: Rc<T>) -> Option<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    astar_search_internal(start, |v| v.distance(&goal) == 0, |v| v.distance(&goal)).0
+}
+
+/// Same as `astar_search`, but estimating the remaining cost to `goal` using
+/// `heuristic` instead of `Vertex::distance`. The found path is only
+/// guaranteed to be the shortest if the heuristic is admissible, i.e. never
+/// overestimates the remaining cost. A heuristic always returning 0 makes this
+/// a plain Dijkstra search.
+pub fn astar_search_with<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    heuristic: impl Fn(&T, &T) -> usize,
+) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    astar_search_internal(start, |v| v.distance(&goal) == 0, |v| heuristic(v, &goal))
+        .0
+        .map(|(path, _)| path)
+}
+
+/// Search for the shortest path from `start` to whichever of `goals` is
+/// closest, using the smallest `Vertex::distance` to any goal as heuristic.
+/// Returns `None` if no goal is reachable, or if `goals` is empty.
+pub fn astar_search_multi<T>(start: Rc<T>, goals: &HashSet<Rc<T>>) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if goals.is_empty() {
+        return None;
+    }
+    let heuristic = |v: &T| goals.iter().map(|g| v.distance(g)).min().unwrap();
+    astar_search_internal(start, |v| goals.contains(v), heuristic)
+        .0
+        .map(|(path, _)| path)
+}
+
+/// Search for the shortest path between two points on an implicit grid, where
+/// `passable` decides which points can be entered. Moves are orthogonal, or
+/// also diagonal if `diagonal` is set, with the manhattan or chebyshev
+/// distance as heuristic respectively. Saves wrapping points in a `Vertex`.
+pub fn grid_astar(
+    start: Cartesian,
+    goal: Cartesian,
+    passable: impl Fn(&Cartesian) -> bool,
+    diagonal: bool,
+) -> Option<Vec<Cartesian>> {
+    let start = Rc::new(GridStep {
+        point: start,
+        passable: &passable,
+        diagonal,
+    });
+    let heuristic = |v: &GridStep| v.distance_to(&goal);
+    astar_search_internal(start, |v| v.point == goal, heuristic)
+        .0
+        .map(|(path, _)| path.iter().map(|v| v.point).collect())
+}
+
+/// A point on the grid searched by `grid_astar`
+struct GridStep<'a> {
+    point: Cartesian,
+    passable: &'a dyn Fn(&Cartesian) -> bool,
+    diagonal: bool,
+}
+
+impl<'a> GridStep<'a> {
+    fn distance_to(&self, other: &Cartesian) -> usize {
+        if self.diagonal {
+            self.point.chebyshev_distance(other)
+        } else {
+            self.point.manhattan_distance(other)
+        }
+    }
+}
+
+impl<'a> Vertex for GridStep<'a> {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        let points = if self.diagonal {
+            self.point.neigh8()
+        } else {
+            self.point.neigh4()
+        };
+        points
+            .into_iter()
+            .filter(|p| (self.passable)(p))
+            .map(|point| {
+                Rc::new(GridStep {
+                    point,
+                    passable: self.passable,
+                    diagonal: self.diagonal,
+                })
+            })
+            .collect()
+    }
+
+    fn distance(&self, other: &Self) -> usize {
+        self.distance_to(&other.point)
+    }
+}
+
+impl<'a> PartialEq for GridStep<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.point == other.point
+    }
+}
+
+impl<'a> Eq for GridStep<'a> {}
+
+impl<'a> Hash for GridStep<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.point.hash(state);
+    }
+}
+
+impl<'a> Debug for GridStep<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.point.fmt(f)
+    }
+}
+
+/// Search for the shortest path from `start` to `goal` through states of any
+/// type, where `neighbors` lists the states reachable in one unit cost step and
+/// `heuristic(state, goal)` estimates the remaining cost. Saves implementing
+/// `Vertex` for quick searches, or for states that can't easily be shared
+/// through `Rc`. The path is only guaranteed to be the shortest if the
+/// heuristic never overestimates.
+pub fn search_with<S>(
+    start: S,
+    goal: S,
+    neighbors: impl Fn(&S) -> Vec<S>,
+    heuristic: impl Fn(&S, &S) -> usize,
+) -> Option<Vec<S>>
+where
+    S: Hash + Eq + Clone,
+{
+    let start = Rc::new(ClosureStep {
+        state: start,
+        neighbors: &neighbors,
+    });
+    astar_search_internal(start, |v| v.state == goal, |v| heuristic(&v.state, &goal))
+        .0
+        .map(|(path, _)| path.iter().map(|v| v.state.clone()).collect())
+}
+
+/// A state searched by `search_with`
+struct ClosureStep<'a, S> {
+    state: S,
+    neighbors: &'a dyn Fn(&S) -> Vec<S>,
+}
+
+impl<'a, S> Vertex for ClosureStep<'a, S> {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        (self.neighbors)(&self.state)
+            .into_iter()
+            .map(|state| {
+                Rc::new(ClosureStep {
+                    state,
+                    neighbors: self.neighbors,
+                })
+            })
+            .collect()
+    }
+
+    /* The heuristic is given to the search directly */
+    fn distance(&self, _other: &Self) -> usize {
+        0
+    }
+}
+
+impl<'a, S: PartialEq> PartialEq for ClosureStep<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl<'a, S: Eq> Eq for ClosureStep<'a, S> {}
+
+impl<'a, S: Hash> Hash for ClosureStep<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+    }
+}
+
+/// States aren't required to be `Debug`
+impl<'a, S> Debug for ClosureStep<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ClosureStep")
+    }
+}
+
+/// Counters describing the work done by a search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of vertices popped from the open set
+    pub expanded: usize,
+    /// Largest size of the open set during the search
+    pub max_open: usize,
+    /// Number of times a vertex was pushed to the open set after already being queued
+    pub reopened: usize,
+}
+
+/// Same as `astar_search`, but also returns statistics of the search, useful
+/// when tuning heuristics.
+pub fn astar_search_with_stats<T>(start: Rc<T>, goal: Rc<T>) -> (Option<Vec<Rc<T>>>, SearchStats)
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let (result, stats) =
+        astar_search_internal(start, |v| v.distance(&goal) == 0, |v| v.distance(&goal));
+    (result.map(|(path, _)| path), stats)
+}
+
+/// A path along with its cost
+type CostedPath<T> = (Vec<Rc<T>>, usize);
+
+/// Error for when a search gave up after expanding as many vertices as it was
+/// allowed to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchLimitExceeded;
+
+/// Same as `astar_search`, but gives up with `SearchLimitExceeded` once
+/// `max_expansions` vertices have been popped from the open set without
+/// reaching the goal. `Ok(None)` means the goal is unreachable.
+pub fn astar_search_bounded<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    max_expansions: usize,
+) -> Result<Option<Vec<Rc<T>>>, SearchLimitExceeded>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let (result, _) = astar_search_limited(
+        start,
+        |v| v.distance(&goal) == 0,
+        |v| v.distance(&goal),
+        |_| false,
+        max_expansions,
+        &mut HashSet::new(),
+    );
+    result.map(|found| found.map(|(path, _)| path))
+}
+
+/// Same as `astar_search`, but treating every vertex in `blocked` as removed
+/// from the graph, so one graph can be reused to try different obstacles.
+/// Returns `None` if `start` or `goal` is blocked.
+pub fn astar_search_masked<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    blocked: &HashSet<Rc<T>>,
+) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if blocked.contains(&start) || blocked.contains(&goal) {
+        return None;
+    }
+    let (result, _) = astar_search_limited(
+        start,
+        |v| v.distance(&goal) == 0,
+        |v| v.distance(&goal),
+        |v| blocked.contains(v),
+        usize::MAX,
+        &mut HashSet::new(),
+    );
+    result.unwrap_or(None).map(|(path, _)| path)
+}
+
+/// Same as `astar_search`, but on failure returns every vertex that was
+/// explored instead of just `None`, to help diagnose why the goal wasn't
+/// reached. As the search gives up only once nothing more can be explored,
+/// that is every vertex reachable from `start`.
+pub fn astar_search_explored<T>(start: Rc<T>, goal: Rc<T>) -> Result<Vec<Rc<T>>, HashSet<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut closed = HashSet::new();
+    let (result, _) = astar_search_limited(
+        start,
+        |v| v.distance(&goal) == 0,
+        |v| v.distance(&goal),
+        |_| false,
+        usize::MAX,
+        &mut closed,
+    );
+    match result {
+        Ok(Some((path, _))) => Ok(path),
+        _ => Err(closed),
+    }
+}
+
+/// Same as `astar_search`, but returning `None` right away if `start` and
+/// `goal` are in different components according to `components`, as given by
+/// `component_ids`. Saves exhausting the component of `start` when searching
+/// disconnected graphs repeatedly. Vertices missing from `components` are
+/// searched for as usual.
+pub fn astar_search_components<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    components: &HashMap<Rc<T>, usize>,
+) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    match (components.get(&start), components.get(&goal)) {
+        (Some(a), Some(b)) if a != b => None,
+        _ => astar_search(start, goal),
+    }
+}
+
+/// A* search from `start` until a vertex satisfying `is_goal` is reached, where
+/// `heuristic` estimates the remaining cost from a vertex
+fn astar_search_internal<T>(
+    start: Rc<T>,
+    is_goal: impl Fn(&T) -> bool,
+    heuristic: impl Fn(&T) -> usize,
+) -> (Option<CostedPath<T>>, SearchStats)
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let (result, stats) = astar_search_limited(
+        start,
+        is_goal,
+        heuristic,
+        |_| false,
+        usize::MAX,
+        &mut HashSet::new(),
+    );
+    (result.unwrap_or(None), stats)
+}
+
+/// A* search like `astar_search_internal`, never entering vertices for which
+/// `blocked` holds, and failing once `max_expansions` vertices have been
+/// expanded. Every expanded vertex, except a reached goal, is added to `closed`.
+fn astar_search_limited<T>(
+    start: Rc<T>,
+    is_goal: impl Fn(&T) -> bool,
+    heuristic: impl Fn(&T) -> usize,
+    blocked: impl Fn(&Rc<T>) -> bool,
+    max_expansions: usize,
+    closed: &mut HashSet<Rc<T>>,
+) -> (
+    Result<Option<CostedPath<T>>, SearchLimitExceeded>,
+    SearchStats,
+)
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut stats = SearchStats::default();
     let mut open = BinaryHeap::<ScoredVertex<T>>::new();
-    let mut closed = HashSet::<Rc<T>>::new();
     let mut came_from = HashMap::<Rc<T>, Rc<T>>::new();
 
     /* g_score, cost of getting from start to that node */
@@ -115,63 +811,2753 @@ where
     /* f_score, cost of gett from start to finish by passing that node */
     let mut f_score = HashMap::<Rc<T>, usize>::new();
 
-    open.push(ScoredVertex::new(start.clone(), usize::MAX));
+    let mut sequence = 0;
+    open.push(ScoredVertex::new(start.clone(), usize::MAX, sequence));
     g_score.entry(start.clone()).or_insert(0);
     f_score.entry(start.clone()).or_insert(usize::MAX);
 
     while !open.is_empty() {
+        if stats.expanded == max_expansions {
+            return (Err(SearchLimitExceeded), stats);
+        }
         let current = open.pop().unwrap();
-        if current.vertex.distance(&*goal) == 0 {
+        stats.expanded += 1;
+        if is_goal(&current.vertex) {
             // Path found, reconstruct path
-            return Some(reconstruct_path(current.vertex, &came_from));
+            let cost = g_score[&current.vertex];
+            let path = reconstruct_path(current.vertex, &came_from);
+            return (Ok(Some((path, cost))), stats);
         }
 
         closed.insert(current.vertex.clone());
-        for neighbor in current
+        for (neighbor, cost) in current
             .vertex
-            .neighbors()
+            .neighbors_with_cost()
             .iter()
-            .filter(|&n| !closed.contains(n))
+            .filter(|&(n, _)| !closed.contains(n) && !blocked(n))
         {
-            let tentative_gscore = g_score[&current.vertex] + 1;
-            let tentative_fscore = tentative_gscore + neighbor.distance(goal.as_ref());
+            let tentative_gscore = g_score[&current.vertex] + cost;
+            let tentative_fscore = tentative_gscore + heuristic(neighbor);
 
-            open.push(ScoredVertex::new(neighbor.clone(), tentative_fscore));
-            if tentative_gscore < *g_score.entry(neighbor.clone()).or_insert(usize::MAX) {
+            /* Only queue the neighbor if this is a better way of reaching it */
+            if tentative_gscore < *g_score.get(neighbor).unwrap_or(&usize::MAX) {
+                if g_score.contains_key(neighbor) {
+                    stats.reopened += 1;
+                }
                 g_score.insert(neighbor.clone(), tentative_gscore);
                 f_score.insert(neighbor.clone(), tentative_fscore);
                 came_from.insert(neighbor.clone(), current.vertex.clone());
+                sequence += 1;
+                open.push(ScoredVertex::new(
+                    neighbor.clone(),
+                    tentative_fscore,
+                    sequence,
+                ));
+                stats.max_open = stats.max_open.max(open.len());
             }
         }
     }
-    return None;
+    (Ok(None), stats)
 }
 
-// TODO Describe purpose of this function
-pub fn count_paths<T>(node: Rc<T>) -> usize
+/// Search for the cheapest path between two vertices using Dijkstra's algorithm,
+/// weighting each edge by `Vertex::neighbors_with_cost`. Returns the path along
+/// with its total cost. Only non-negative edge weights are supported.
+pub fn dijkstra_search<T>(start: Rc<T>, goal: Rc<T>) -> Option<(Vec<Rc<T>>, usize)>
 where
     T: Vertex + Hash + Eq + Debug,
 {
-    count_paths_internal(&mut HashMap::new(), node)
+    dijkstra_search_internal(start, goal, |_, _| false)
+}
+
+/// Same as `dijkstra_search`, but treating every vertex in `blocked` as
+/// removed from the graph. Returns `None` if `start` or `goal` is blocked.
+pub fn dijkstra_search_masked<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    blocked: &HashSet<Rc<T>>,
+) -> Option<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if blocked.contains(&start) || blocked.contains(&goal) {
+        return None;
+    }
+    dijkstra_search_internal(start, goal, |_, to| blocked.contains(to))
 }
 
-fn count_paths_internal<T>(nodes: &mut HashMap<Rc<T>, usize>, node: Rc<T>) -> usize
+/// Dijkstra's algorithm, ignoring every edge for which `blocked(from, to)` holds
+fn dijkstra_search_internal<T>(
+    start: Rc<T>,
+    goal: Rc<T>,
+    blocked: impl Fn(&Rc<T>, &Rc<T>) -> bool,
+) -> Option<CostedPath<T>>
 where
     T: Vertex + Hash + Eq + Debug,
 {
-    if !nodes.contains_key(&node) {
-        let neighbors = node.neighbors();
-        let mut paths = 0;
-        if neighbors.len() > 0 {
-            for n in neighbors {
-                paths += count_paths_internal(nodes, n.clone());
+    let mut open = BinaryHeap::<ScoredVertex<T>>::new();
+    let mut came_from = HashMap::<Rc<T>, Rc<T>>::new();
+    let mut cost = HashMap::<Rc<T>, usize>::new();
+
+    let mut sequence = 0;
+    open.push(ScoredVertex::new(start.clone(), 0, sequence));
+    cost.insert(start, 0);
+
+    while let Some(current) = open.pop() {
+        if current.vertex == goal {
+            let path = reconstruct_path(current.vertex, &came_from);
+            return Some((path, current.score));
+        }
+        if current.score > cost[&current.vertex] {
+            /* Stale entry, a cheaper way here has already been expanded */
+            continue;
+        }
+
+        for (neighbor, edge_cost) in current.vertex.neighbors_with_cost() {
+            if blocked(&current.vertex, &neighbor) {
+                continue;
             }
-        } else {
-            paths = 1;
+            let tentative_cost = current.score + edge_cost;
+            if tentative_cost < *cost.get(&neighbor).unwrap_or(&usize::MAX) {
+                cost.insert(neighbor.clone(), tentative_cost);
+                came_from.insert(neighbor.clone(), current.vertex.clone());
+                sequence += 1;
+                open.push(ScoredVertex::new(neighbor, tentative_cost, sequence));
+            }
+        }
+    }
+    None
+}
+
+/// Find the `k` cheapest loopless paths from `start` to `goal` using Yen's
+/// algorithm, weighting edges by `Vertex::neighbors_with_cost`. Paths are returned in
+/// nondecreasing cost order, along with their cost. Fewer than `k` paths are
+/// returned if there aren't that many.
+pub fn k_shortest_paths<T>(start: Rc<T>, goal: Rc<T>, k: usize) -> Vec<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut found = Vec::new();
+    if k == 0 {
+        return found;
+    }
+    match dijkstra_search(start, goal.clone()) {
+        Some(path) => found.push(path),
+        None => return found,
+    }
+    let mut candidates: Vec<CostedPath<T>> = Vec::new();
+
+    while found.len() < k {
+        let previous = found.last().unwrap().0.clone();
+        let mut root_cost = 0;
+        for i in 0..previous.len() - 1 {
+            let root = &previous[..=i];
+            let spur = &previous[i];
+
+            /* Remove the next edge of every found path sharing this root, and
+             * the root itself, to force a deviation at the spur vertex */
+            let mut removed_edges = HashSet::<(Rc<T>, Rc<T>)>::new();
+            for (path, _) in &found {
+                if path.len() > i + 1 && path[..=i] == *root {
+                    removed_edges.insert((path[i].clone(), path[i + 1].clone()));
+                }
+            }
+            let removed_nodes = root[..i].iter().cloned().collect::<HashSet<_>>();
+
+            let spur_path = dijkstra_search_internal(spur.clone(), goal.clone(), |from, to| {
+                removed_nodes.contains(to) || removed_edges.contains(&(from.clone(), to.clone()))
+            });
+            if let Some((spur_path, spur_cost)) = spur_path {
+                let mut path = root[..i].to_vec();
+                path.extend(spur_path);
+                let candidate = (path, root_cost + spur_cost);
+                if !candidates.contains(&candidate) && !found.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+            root_cost += step_cost(&*previous[i], &*previous[i + 1]);
+        }
+
+        if candidates.is_empty() {
+            break;
         }
-        nodes.insert(node.clone(), paths);
-        paths
-    } else {
-        *nodes.get(&node).unwrap()
+        let (best, _) = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &(_, cost))| cost)
+            .unwrap();
+        found.push(candidates.remove(best));
+    }
+    found
+}
+
+/// Cost of the cheapest edge from `from` to its neighbor `to`
+fn step_cost<T>(from: &T, to: &T) -> usize
+where
+    T: Vertex + Eq,
+{
+    from.neighbors_with_cost()
+        .into_iter()
+        .filter(|(n, _)| **n == *to)
+        .map(|(_, cost)| cost)
+        .min()
+        .unwrap()
+}
+
+/// Search for the cheapest path between two vertices where every edge costs
+/// either 0 or 1, as given by `Vertex::neighbors_with_cost`. Uses a double ended queue
+/// instead of a heap, pushing free moves to the front and unit moves to the
+/// back. Returns the path along with its total cost.
+pub fn zero_one_bfs<T>(start: Rc<T>, goal: Rc<T>) -> Option<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut queue = VecDeque::<(Rc<T>, usize)>::new();
+    let mut came_from = HashMap::<Rc<T>, Rc<T>>::new();
+    let mut cost = HashMap::<Rc<T>, usize>::new();
+
+    cost.insert(start.clone(), 0);
+    queue.push_back((start, 0));
+
+    while let Some((current, current_cost)) = queue.pop_front() {
+        if current == goal {
+            let path = reconstruct_path(current, &came_from);
+            return Some((path, current_cost));
+        }
+        if current_cost > cost[&current] {
+            /* Stale entry, a cheaper way here has already been expanded */
+            continue;
+        }
+
+        for (neighbor, edge_cost) in current.neighbors_with_cost() {
+            debug_assert!(edge_cost <= 1, "Edge cost must be 0 or 1");
+            let tentative_cost = current_cost + edge_cost;
+            if tentative_cost < *cost.get(&neighbor).unwrap_or(&usize::MAX) {
+                cost.insert(neighbor.clone(), tentative_cost);
+                came_from.insert(neighbor.clone(), current.clone());
+                if edge_cost == 0 {
+                    queue.push_front((neighbor, tentative_cost));
+                } else {
+                    queue.push_back((neighbor, tentative_cost));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Depth first traversal of every vertex reachable from `start`, calling
+/// `visit` once per vertex in the order they are discovered.
+pub fn dfs<T>(start: Rc<T>, mut visit: impl FnMut(&Rc<T>))
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    dfs_timed(start, |v| visit(v), |_| {});
+}
+
+/// Depth first traversal of every vertex reachable from `start`. `on_enter` is
+/// called when a vertex is discovered, and `on_leave` once all vertices reachable
+/// through it have been finished. Uses an explicit stack rather than recursion,
+/// so deep graphs won't overflow the call stack.
+pub fn dfs_timed<T>(
+    start: Rc<T>,
+    mut on_enter: impl FnMut(&Rc<T>),
+    mut on_leave: impl FnMut(&Rc<T>),
+) where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut visited = HashSet::<Rc<T>>::new();
+    let mut stack = Vec::new();
+
+    on_enter(&start);
+    visited.insert(start.clone());
+    let neighbors = start.neighbors().into_iter();
+    stack.push((start, neighbors));
+
+    while let Some((_, neighbors)) = stack.last_mut() {
+        match neighbors.next() {
+            Some(n) => {
+                if visited.insert(n.clone()) {
+                    on_enter(&n);
+                    let neighbors = n.neighbors().into_iter();
+                    stack.push((n, neighbors));
+                }
+            }
+            None => {
+                let (node, _) = stack.pop().unwrap();
+                on_leave(&node);
+            }
+        }
+    }
+}
+
+/// Every vertex reachable from `start` that has no neighbors, in depth first
+/// discovery order. Vertices reachable along several paths are listed once.
+pub fn leaves<T>(start: Rc<T>) -> Vec<Rc<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut leaves = Vec::new();
+    dfs(start, |v| {
+        if v.neighbors().is_empty() {
+            leaves.push(v.clone());
+        }
+    });
+    leaves
+}
+
+/// Search for a shortest path from `start` to `goal` by iterative deepening,
+/// running depth-limited DFS with increasing limits up to `max_depth` edges.
+/// Finds the same path lengths as BFS while only keeping the current path in
+/// memory. Returns `None` if `goal` isn't within `max_depth` of `start`.
+pub fn iddfs<T>(start: Rc<T>, goal: Rc<T>, max_depth: usize) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if start == goal {
+        return Some(vec![start]);
+    }
+    for limit in 1..=max_depth {
+        let mut on_path = HashSet::<Rc<T>>::new();
+        let mut stack = Vec::new();
+        let mut cut_off = false;
+
+        on_path.insert(start.clone());
+        stack.push((start.clone(), start.neighbors().into_iter()));
+        while let Some((_, neighbors)) = stack.last_mut() {
+            match neighbors.next() {
+                Some(n) => {
+                    if on_path.contains(&n) {
+                        continue;
+                    }
+                    if n == goal {
+                        let mut path = stack.into_iter().map(|(v, _)| v).collect::<Vec<_>>();
+                        path.push(n);
+                        return Some(path);
+                    }
+                    if stack.len() < limit {
+                        on_path.insert(n.clone());
+                        let neighbors = n.neighbors().into_iter();
+                        stack.push((n, neighbors));
+                    } else {
+                        cut_off = true;
+                    }
+                }
+                None => {
+                    let (node, _) = stack.pop().unwrap();
+                    on_path.remove(&node);
+                }
+            }
+        }
+        if !cut_off {
+            /* Everything reachable was explored without hitting the limit */
+            return None;
+        }
+    }
+    None
+}
+
+/// Walk up to `steps` edges from `start`, each time moving to a uniformly random
+/// neighbor, returning the visited vertices starting with `start`. The walk
+/// stops early at a vertex without neighbors.
+#[cfg(feature = "rand")]
+pub fn random_walk<T>(start: Rc<T>, steps: usize, rng: &mut impl Rng) -> Vec<Rc<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut walk = vec![start];
+    for _ in 0..steps {
+        let next = match rng.choose(&walk.last().unwrap().neighbors()) {
+            Some(n) => n.clone(),
+            None => break,
+        };
+        walk.push(next);
+    }
+    walk
+}
+
+/// Error for when a cycle is found in a graph expected to be acyclic.
+/// `node` is one of the vertices on the cycle.
+#[derive(Debug)]
+pub struct CycleError<T> {
+    pub node: Rc<T>,
+}
+
+/// Order all vertices reachable from `nodes` such that every vertex comes
+/// before its neighbors. As there is no registry of all vertices, every
+/// source of the graph needs to be among `nodes`.
+pub fn topological_sort<T>(nodes: &[Rc<T>]) -> Result<Vec<Rc<T>>, CycleError<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    /* false while a vertex is being expanded, true once it is finished */
+    let mut finished = HashMap::<Rc<T>, bool>::new();
+    let mut order = Vec::new();
+
+    for node in nodes {
+        if finished.contains_key(node) {
+            continue;
+        }
+        finished.insert(node.clone(), false);
+        let mut stack = vec![(node.clone(), node.neighbors().into_iter())];
+        while let Some((_, neighbors)) = stack.last_mut() {
+            match neighbors.next() {
+                Some(n) => match finished.get(&n).cloned() {
+                    Some(false) => return Err(CycleError { node: n }),
+                    Some(true) => {}
+                    None => {
+                        finished.insert(n.clone(), false);
+                        let neighbors = n.neighbors().into_iter();
+                        stack.push((n, neighbors));
+                    }
+                },
+                None => {
+                    let (v, _) = stack.pop().unwrap();
+                    finished.insert(v.clone(), true);
+                    order.push(v);
+                }
+            }
+        }
+    }
+
+    order.reverse();
+    Ok(order)
+}
+
+/// Find the longest path among the vertices reachable from `nodes`, weighting
+/// edges by `Vertex::neighbors_with_cost`. Only acyclic graphs are supported, as the
+/// problem is NP-hard in general, so `None` is returned if there is a cycle.
+pub fn longest_path_dag<T>(nodes: &[Rc<T>]) -> Option<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let order = topological_sort(nodes).ok()?;
+    let mut length = HashMap::<Rc<T>, usize>::new();
+    let mut came_from = HashMap::<Rc<T>, Rc<T>>::new();
+
+    for node in &order {
+        let d = *length.entry(node.clone()).or_insert(0);
+        for (n, edge_cost) in node.neighbors_with_cost() {
+            let tentative = d + edge_cost;
+            match length.get(&n) {
+                Some(&l) if l >= tentative => {}
+                _ => {
+                    length.insert(n.clone(), tentative);
+                    came_from.insert(n, node.clone());
+                }
+            }
+        }
+    }
+
+    let end = order.iter().max_by_key(|n| length[*n])?;
+    Some((reconstruct_path(end.clone(), &came_from), length[end]))
+}
+
+/// Find the cheapest path starting at `nodes[0]` and visiting every vertex in
+/// `nodes` exactly once, returning to `nodes[0]` at the end if
+/// `return_to_start` is set. Only direct edges between the given vertices are
+/// used, weighted by `Vertex::neighbors_with_cost`, so for sparse graphs the distances
+/// between the interesting vertices should be computed first. Uses the
+/// Held-Karp dynamic program, which takes `O(2^n * n^2)` time and `O(2^n * n)`
+/// memory, so more than about 20 vertices is impractical.
+pub fn shortest_hamiltonian_path<T>(
+    nodes: &[Rc<T>],
+    return_to_start: bool,
+) -> Option<(Vec<Rc<T>>, usize)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let n = nodes.len();
+    if n == 0 {
+        return None;
+    }
+    let index = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (v.clone(), i))
+        .collect::<HashMap<_, _>>();
+    let mut cost = vec![vec![None; n]; n];
+    for (i, v) in nodes.iter().enumerate() {
+        for (neighbor, c) in v.neighbors_with_cost() {
+            if let Some(&j) = index.get(&neighbor) {
+                cost[i][j] = Some(cost[i][j].map_or(c, |old: usize| old.min(c)));
+            }
+        }
+    }
+
+    /* best[visited][last] is the cheapest way from the start through the
+     * vertices in the visited bitmask, ending at last */
+    let full = (1 << n) - 1;
+    let mut best = vec![vec![None::<usize>; n]; 1 << n];
+    let mut came_from = vec![vec![0; n]; 1 << n];
+    best[1][0] = Some(0);
+    for visited in 1..=full {
+        for last in 0..n {
+            let so_far = match best[visited][last] {
+                Some(c) => c,
+                None => continue,
+            };
+            for next in 0..n {
+                let edge = match cost[last][next] {
+                    Some(c) if visited & (1 << next) == 0 => c,
+                    _ => continue,
+                };
+                let to = visited | (1 << next);
+                match best[to][next] {
+                    Some(c) if c <= so_far + edge => {}
+                    _ => {
+                        best[to][next] = Some(so_far + edge);
+                        came_from[to][next] = last;
+                    }
+                }
+            }
+        }
+    }
+
+    let closing = |last: usize| {
+        if !return_to_start || n == 1 {
+            Some(0)
+        } else {
+            cost[last][0]
+        }
+    };
+    let (total, mut last) = (0..n)
+        .filter_map(|last| Some((best[full][last]? + closing(last)?, last)))
+        .min()?;
+
+    let mut path = Vec::with_capacity(n + 1);
+    let mut visited = full;
+    while visited != 1 {
+        path.push(nodes[last].clone());
+        let prev = came_from[visited][last];
+        visited &= !(1 << last);
+        last = prev;
+    }
+    path.push(nodes[0].clone());
+    path.reverse();
+    if return_to_start && n > 1 {
+        path.push(nodes[0].clone());
+    }
+    Some((path, total))
+}
+
+/// Partition all vertices reachable from `nodes` into strongly connected
+/// components using Tarjan's algorithm, with an explicit stack rather than
+/// recursion. Components are returned in reverse topological order, i.e. no
+/// component has an edge into a component after it.
+pub fn strongly_connected_components<T>(nodes: &[Rc<T>]) -> Vec<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut index = HashMap::<Rc<T>, usize>::new();
+    let mut low = HashMap::<Rc<T>, usize>::new();
+    let mut on_stack = HashSet::<Rc<T>>::new();
+    let mut component_stack = Vec::new();
+    let mut components = Vec::new();
+
+    for root in nodes {
+        if index.contains_key(root) {
+            continue;
+        }
+        index.insert(root.clone(), index.len());
+        low.insert(root.clone(), index[root]);
+        on_stack.insert(root.clone());
+        component_stack.push(root.clone());
+        let mut stack = vec![(root.clone(), root.neighbors().into_iter())];
+
+        while let Some((node, neighbors)) = stack.last_mut() {
+            match neighbors.next() {
+                Some(n) => {
+                    if !index.contains_key(&n) {
+                        index.insert(n.clone(), index.len());
+                        low.insert(n.clone(), index[&n]);
+                        on_stack.insert(n.clone());
+                        component_stack.push(n.clone());
+                        let neighbors = n.neighbors().into_iter();
+                        stack.push((n, neighbors));
+                    } else if on_stack.contains(&n) {
+                        let l = low.get_mut(&*node).unwrap();
+                        *l = (*l).min(index[&n]);
+                    }
+                }
+                None => {
+                    let (node, _) = stack.pop().unwrap();
+                    let node_low = low[&node];
+                    if let Some((parent, _)) = stack.last() {
+                        let l = low.get_mut(parent).unwrap();
+                        *l = (*l).min(node_low);
+                    }
+                    if node_low == index[&node] {
+                        let mut component = Vec::new();
+                        loop {
+                            let v = component_stack.pop().unwrap();
+                            on_stack.remove(&v);
+                            let done = v == node;
+                            component.push(v);
+                            if done {
+                                break;
+                            }
+                        }
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
+
+/// Check if there is a cycle among the vertices reachable from `start`
+pub fn has_cycle<T>(start: Rc<T>) -> bool
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    find_cycle(start).is_some()
+}
+
+/// Find a cycle among the vertices reachable from `start`. The cycle is returned
+/// in traversal order, implicitly closed by an edge from the last vertex back to
+/// the first. Vertices are colored white (unseen), gray (on the DFS stack) and
+/// black (finished), and reaching a gray vertex means a back edge was found.
+pub fn find_cycle<T>(start: Rc<T>) -> Option<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    /* Vertices not in the map are white, false is gray and true is black */
+    let mut finished = HashMap::<Rc<T>, bool>::new();
+
+    finished.insert(start.clone(), false);
+    let neighbors = start.neighbors().into_iter();
+    let mut stack = vec![(start, neighbors)];
+    while let Some((_, neighbors)) = stack.last_mut() {
+        match neighbors.next() {
+            Some(n) => match finished.get(&n).cloned() {
+                Some(false) => {
+                    let first = stack.iter().position(|(v, _)| *v == n).unwrap();
+                    return Some(stack.drain(first..).map(|(v, _)| v).collect());
+                }
+                Some(true) => {}
+                None => {
+                    finished.insert(n.clone(), false);
+                    let neighbors = n.neighbors().into_iter();
+                    stack.push((n, neighbors));
+                }
+            },
+            None => {
+                let (v, _) = stack.pop().unwrap();
+                finished.insert(v, true);
+            }
+        }
+    }
+    None
+}
+
+/// Error for when a negative cycle is reachable, making the shortest distance
+/// unbounded. `node` is a vertex whose distance could still be lowered.
+#[derive(Debug)]
+pub struct NegativeCycleError<T> {
+    pub node: Rc<T>,
+}
+
+/// Calculate the shortest distance from `start` to every reachable vertex using
+/// Bellman-Ford, weighting edges by `Vertex::signed_edge_cost`. Unlike
+/// `dijkstra_search` negative costs are allowed. `nodes` has to contain every
+/// vertex of the graph, as edges are only taken from those.
+pub fn bellman_ford<T>(
+    nodes: &[Rc<T>],
+    start: Rc<T>,
+) -> Result<HashMap<Rc<T>, i64>, NegativeCycleError<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let edges = nodes
+        .iter()
+        .flat_map(|from| {
+            from.neighbors().into_iter().map(move |to| {
+                let cost = from.signed_edge_cost(&to);
+                (from.clone(), to, cost)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut dist = HashMap::<Rc<T>, i64>::new();
+    dist.insert(start, 0);
+
+    /* Improve distances until nothing changes, or |V| - 1 rounds have passed */
+    for _ in 1..nodes.len().max(1) {
+        let mut changed = false;
+        for (from, to, cost) in &edges {
+            if let Some(&d) = dist.get(from) {
+                if d + cost < *dist.get(to).unwrap_or(&i64::MAX) {
+                    dist.insert(to.clone(), d + cost);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return Ok(dist);
+        }
+    }
+
+    /* Any further improvement means there is a negative cycle */
+    for (from, to, cost) in &edges {
+        if let Some(&d) = dist.get(from) {
+            if d + cost < *dist.get(to).unwrap_or(&i64::MAX) {
+                return Err(NegativeCycleError { node: to.clone() });
+            }
+        }
+    }
+    Ok(dist)
+}
+
+/// Build an undirected adjacency map of every vertex reachable from `nodes`,
+/// where each edge given by `neighbors` is also added in reverse.
+fn undirected_adjacency<T>(nodes: &[Rc<T>]) -> HashMap<Rc<T>, Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut adjacency = HashMap::<Rc<T>, Vec<Rc<T>>>::new();
+    let mut stack = nodes.to_vec();
+    let mut expanded = HashSet::<Rc<T>>::new();
+
+    while let Some(node) = stack.pop() {
+        if !expanded.insert(node.clone()) {
+            continue;
+        }
+        adjacency.entry(node.clone()).or_default();
+        for n in node.neighbors() {
+            if !adjacency[&node].contains(&n) {
+                adjacency.get_mut(&node).unwrap().push(n.clone());
+            }
+            let reverse = adjacency.entry(n.clone()).or_default();
+            if !reverse.contains(&node) {
+                reverse.push(node.clone());
+            }
+            stack.push(n);
+        }
+    }
+    adjacency
+}
+
+/// Number of edges out of every vertex reachable from `nodes`
+pub fn out_degrees<T>(nodes: &[Rc<T>]) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    multi_source_bfs(nodes)
+        .into_keys()
+        .map(|v| {
+            let degree = v.neighbors().len();
+            (v, degree)
+        })
+        .collect()
+}
+
+/// Number of edges into every vertex reachable from `nodes`, including
+/// vertices without incoming edges. As edges can only be followed forwards,
+/// this requires scanning every edge of the reachable graph.
+pub fn in_degrees<T>(nodes: &[Rc<T>]) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    reverse_adjacency(nodes)
+        .into_iter()
+        .map(|(v, predecessors)| (v, predecessors.len()))
+        .collect()
+}
+
+/// The transpose of the graph reachable from `nodes`, mapping every vertex to
+/// the vertices with an edge into it. Vertices without incoming edges map to
+/// an empty list.
+pub fn reverse_adjacency<T>(nodes: &[Rc<T>]) -> HashMap<Rc<T>, Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let reachable = multi_source_bfs(nodes);
+    let mut reverse = reachable
+        .keys()
+        .map(|v| (v.clone(), Vec::new()))
+        .collect::<HashMap<_, _>>();
+    for v in reachable.keys() {
+        for n in v.neighbors() {
+            reverse.get_mut(&n).unwrap().push(v.clone());
+        }
+    }
+    reverse
+}
+
+/// Partition all vertices reachable from `nodes` into connected components,
+/// treating edges as undirected. Components are ordered by their first vertex
+/// in `nodes`.
+pub fn connected_components<T>(nodes: &[Rc<T>]) -> Vec<Vec<Rc<T>>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let adjacency = undirected_adjacency(nodes);
+    let mut visited = HashSet::<Rc<T>>::new();
+    let mut components = Vec::new();
+
+    for node in nodes {
+        if visited.contains(node) {
+            continue;
+        }
+        let mut component = Vec::new();
+        let mut queue = VecDeque::new();
+        visited.insert(node.clone());
+        queue.push_back(node.clone());
+        while let Some(current) = queue.pop_front() {
+            for n in &adjacency[&current] {
+                if visited.insert(n.clone()) {
+                    queue.push_back(n.clone());
+                }
+            }
+            component.push(current);
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Map every vertex of `components`, as returned by `connected_components`, to
+/// the index of its component. Vertices in different components can't reach
+/// each other, while being in the same one only means they are connected when
+/// ignoring edge directions.
+pub fn component_ids<T>(components: &[Vec<Rc<T>>]) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, component)| component.iter().map(move |v| (v.clone(), i)))
+        .collect()
+}
+
+/// Find the vertices whose removal would disconnect the component they are in,
+/// among all vertices reachable from `nodes`, treating edges as undirected.
+/// The root of each DFS tree is special cased, as it has no parent to be cut
+/// off from. It is only an articulation point if it has more than one child in
+/// the DFS tree.
+pub fn articulation_points<T>(nodes: &[Rc<T>]) -> HashSet<Rc<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    low_link(nodes).0
+}
+
+/// Find the edges whose removal would disconnect the component they are in,
+/// among all vertices reachable from `nodes`, treating edges as undirected.
+/// Each bridge is given in the order it was traversed by the DFS.
+pub fn bridges<T>(nodes: &[Rc<T>]) -> Vec<(Rc<T>, Rc<T>)>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    low_link(nodes).1
+}
+
+type Edge<T> = (Rc<T>, Rc<T>);
+
+/// Iterative DFS over the undirected graph reachable from `nodes`, computing
+/// discovery times and low links. Returns the articulation points and bridges.
+fn low_link<T>(nodes: &[Rc<T>]) -> (HashSet<Rc<T>>, Vec<Edge<T>>)
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let adjacency = undirected_adjacency(nodes);
+    let mut discovered = HashMap::<Rc<T>, usize>::new();
+    let mut low = HashMap::<Rc<T>, usize>::new();
+    let mut points = HashSet::new();
+    let mut bridges = Vec::new();
+
+    for root in nodes {
+        if discovered.contains_key(root) {
+            continue;
+        }
+        discovered.insert(root.clone(), discovered.len());
+        low.insert(root.clone(), discovered[root]);
+        let mut root_children = 0;
+        let mut stack = vec![(root.clone(), None::<Rc<T>>, 0)];
+
+        while let Some((node, parent, next)) = stack.last_mut() {
+            let neighbors = &adjacency[&*node];
+            if *next < neighbors.len() {
+                let n = neighbors[*next].clone();
+                *next += 1;
+                if parent.as_ref() == Some(&n) {
+                    continue;
+                }
+                match discovered.get(&n) {
+                    Some(&time) => {
+                        let l = low.get_mut(&*node).unwrap();
+                        *l = (*l).min(time);
+                    }
+                    None => {
+                        let parent = node.clone();
+                        discovered.insert(n.clone(), discovered.len());
+                        low.insert(n.clone(), discovered[&n]);
+                        stack.push((n, Some(parent), 0));
+                    }
+                }
+            } else {
+                let (node, parent, _) = stack.pop().unwrap();
+                let parent = match parent {
+                    Some(parent) => parent,
+                    None => continue,
+                };
+                let node_low = low[&node];
+                let l = low.get_mut(&parent).unwrap();
+                *l = (*l).min(node_low);
+                if node_low > discovered[&parent] {
+                    bridges.push((parent.clone(), node));
+                }
+                if parent == *root {
+                    root_children += 1;
+                } else if node_low >= discovered[&parent] {
+                    points.insert(parent);
+                }
+            }
+        }
+        if root_children > 1 {
+            points.insert(root.clone());
+        }
+    }
+    (points, bridges)
+}
+
+/// Split all vertices reachable from `nodes` into two sides, such that every
+/// edge goes between the sides, treating edges as undirected. Returns `None`
+/// if that is impossible, i.e. there is a cycle of odd length.
+pub fn two_coloring<T>(nodes: &[Rc<T>]) -> Option<HashMap<Rc<T>, bool>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let adjacency = undirected_adjacency(nodes);
+    let mut coloring = HashMap::new();
+
+    for node in nodes {
+        if coloring.contains_key(node) {
+            continue;
+        }
+        let mut queue = VecDeque::new();
+        coloring.insert(node.clone(), false);
+        queue.push_back(node.clone());
+        while let Some(current) = queue.pop_front() {
+            let color = coloring[&current];
+            for n in &adjacency[&current] {
+                match coloring.get(n) {
+                    Some(&c) if c == color => return None,
+                    Some(_) => {}
+                    None => {
+                        coloring.insert(n.clone(), !color);
+                        queue.push_back(n.clone());
+                    }
+                }
+            }
+        }
+    }
+    Some(coloring)
+}
+
+/// Color all vertices reachable from `nodes` such that no two adjacent vertices
+/// share a color, treating edges as undirected. Colors are numbered from 0.
+/// Vertices are colored greedily in order of decreasing degree, ties broken by
+/// BFS order from `nodes`, each getting the smallest color not used by its
+/// neighbors. This isn't guaranteed to use the minimum number of colors, as
+/// finding that is NP-hard.
+pub fn greedy_coloring<T>(nodes: &[Rc<T>]) -> HashMap<Rc<T>, usize>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let adjacency = undirected_adjacency(nodes);
+    let mut order = connected_components(nodes).concat();
+    order.sort_by(|a, b| adjacency[b].len().cmp(&adjacency[a].len()));
+
+    let mut coloring = HashMap::new();
+    for v in order {
+        let used = adjacency[&v]
+            .iter()
+            .filter_map(|n| coloring.get(n))
+            .collect::<HashSet<_>>();
+        let color = (0..).find(|c| !used.contains(c)).unwrap();
+        coloring.insert(v, color);
+    }
+    coloring
+}
+
+/// Number of distinct colors used by a coloring, such as from `greedy_coloring`
+pub fn color_count<T>(coloring: &HashMap<Rc<T>, usize>) -> usize
+where
+    T: Hash + Eq,
+{
+    coloring.values().collect::<HashSet<_>>().len()
+}
+
+/// An edge between two vertices, along with its weight
+pub type WeightedEdge<T> = (Rc<T>, Rc<T>, usize);
+
+/// Find a minimum spanning tree using Kruskal's algorithm, given an explicit
+/// list of undirected weighted edges. Returns the chosen edges along with their
+/// total weight. For disconnected input this is a minimum spanning forest.
+pub fn kruskal<T>(edges: &[WeightedEdge<T>]) -> (Vec<WeightedEdge<T>>, usize)
+where
+    T: Hash + Eq + Debug,
+{
+    let mut sorted = edges.to_vec();
+    sorted.sort_by_key(|&(_, _, weight)| weight);
+
+    let mut sets = DisjointSet::new();
+    let mut tree = Vec::new();
+    let mut total = 0;
+    for (a, b, weight) in sorted {
+        if sets.union(&a, &b) {
+            total += weight;
+            tree.push((a, b, weight));
+        }
+    }
+    (tree, total)
+}
+
+/// Find a minimum spanning tree of the component containing `start` using
+/// Prim's algorithm, weighting edges by `Vertex::neighbors_with_cost`. Edges
+/// are expected to go both ways at the same cost. Returns the chosen edges as
+/// `(from, to, weight)` in the order they were added, where `from` is already
+/// in the tree. Vertices not reachable from `start` are left out.
+pub fn prim_mst<T>(start: Rc<T>) -> Vec<WeightedEdge<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut open = BinaryHeap::<ScoredVertex<T>>::new();
+    let mut in_tree = HashSet::<Rc<T>>::new();
+    /* Cheapest known edge connecting each vertex to the tree */
+    let mut best = HashMap::<Rc<T>, (Rc<T>, usize)>::new();
+    let mut tree = Vec::new();
+
+    let mut sequence = 0;
+    open.push(ScoredVertex::new(start, 0, sequence));
+    while let Some(current) = open.pop() {
+        if !in_tree.insert(current.vertex.clone()) {
+            continue;
+        }
+        if let Some((from, weight)) = best.remove(&current.vertex) {
+            tree.push((from, current.vertex.clone(), weight));
+        }
+
+        for (neighbor, weight) in current.vertex.neighbors_with_cost() {
+            if in_tree.contains(&neighbor) {
+                continue;
+            }
+            match best.get(&neighbor) {
+                Some(&(_, known)) if known <= weight => {}
+                _ => {
+                    best.insert(neighbor.clone(), (current.vertex.clone(), weight));
+                    sequence += 1;
+                    open.push(ScoredVertex::new(neighbor, weight, sequence));
+                }
+            }
+        }
+    }
+    tree
+}
+
+/// Count the number of paths from `node` to a leaf, i.e. a vertex without
+/// neighbors. Path counts are memoized per vertex, so shared parts of the
+/// graph are only traversed once. Fails with `CycleError` if a cycle is
+/// reachable, as there would be infinitely many paths.
+pub fn count_paths<T>(node: Rc<T>) -> Result<usize, CycleError<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    count_paths_internal(&mut HashMap::new(), node)
+}
+
+/// Count the loopless paths from `start` to `goal`, optionally only those of at
+/// most `max_len` edges. Vertices are only excluded while on the current path,
+/// so different paths may share vertices. Beware that the number of paths can
+/// grow exponentially with the size of the graph.
+pub fn count_simple_paths<T>(start: Rc<T>, goal: Rc<T>, max_len: Option<usize>) -> usize
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    if start == goal {
+        return 1;
+    }
+    let max_len = max_len.unwrap_or(usize::MAX);
+    let mut on_path = HashSet::<Rc<T>>::new();
+    let mut stack = Vec::new();
+    let mut paths = 0;
+
+    on_path.insert(start.clone());
+    stack.push((start.clone(), start.neighbors().into_iter()));
+    while let Some((_, neighbors)) = stack.last_mut() {
+        match neighbors.next() {
+            Some(n) => {
+                if on_path.contains(&n) || stack.len() > max_len {
+                    continue;
+                }
+                if n == goal {
+                    paths += 1;
+                } else {
+                    on_path.insert(n.clone());
+                    let neighbors = n.neighbors().into_iter();
+                    stack.push((n, neighbors));
+                }
+            }
+            None => {
+                let (node, _) = stack.pop().unwrap();
+                on_path.remove(&node);
+            }
+        }
+    }
+    paths
+}
+
+fn count_paths_internal<T>(
+    nodes: &mut HashMap<Rc<T>, Option<usize>>,
+    node: Rc<T>,
+) -> Result<usize, CycleError<T>>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    match nodes.get(&node) {
+        Some(&Some(paths)) => Ok(paths),
+        Some(&None) => Err(CycleError { node }),
+        None => {
+            /* Mark as in progress, reaching it again means there is a cycle */
+            nodes.insert(node.clone(), None);
+            let neighbors = node.neighbors();
+            let mut paths = 0;
+            if !neighbors.is_empty() {
+                for n in neighbors {
+                    paths += count_paths_internal(nodes, n)?;
+                }
+            } else {
+                paths = 1;
+            }
+            nodes.insert(node, Some(paths));
+            Ok(paths)
+        }
+    }
+}
+
+/// Precomputed ancestor tables for answering lowest common ancestor queries on
+/// a tree in `O(log n)`, using binary lifting. The tree is found by following
+/// `neighbors` from the root, where neighbors already seen are skipped so
+/// edges may also point back at the parent. If the graph isn't a tree the
+/// answers are meaningless.
+pub struct TreeLca<T>
+where
+    T: Vertex + Hash + Eq,
+{
+    index: HashMap<Rc<T>, usize>,
+    nodes: Vec<Rc<T>>,
+    depth: Vec<usize>,
+    /* ancestors[k][i] is the ancestor 2^k levels above node i, or the root */
+    ancestors: Vec<Vec<usize>>,
+}
+
+impl<T> TreeLca<T>
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    pub fn new(root: Rc<T>) -> TreeLca<T> {
+        let mut index = HashMap::new();
+        let mut nodes = vec![root.clone()];
+        let mut depth = vec![0];
+        let mut parents = vec![0];
+        index.insert(root, 0);
+
+        let mut i = 0;
+        while i < nodes.len() {
+            for n in nodes[i].neighbors() {
+                if !index.contains_key(&n) {
+                    index.insert(n.clone(), nodes.len());
+                    nodes.push(n);
+                    depth.push(depth[i] + 1);
+                    parents.push(i);
+                }
+            }
+            i += 1;
+        }
+
+        let mut ancestors = vec![parents];
+        while 1 << ancestors.len() < nodes.len() {
+            let last = ancestors.last().unwrap();
+            let next = last.iter().map(|&a| last[a]).collect();
+            ancestors.push(next);
+        }
+        TreeLca {
+            index,
+            nodes,
+            depth,
+            ancestors,
+        }
+    }
+
+    /// The deepest vertex having both `a` and `b` as descendants, where a
+    /// vertex counts as its own descendant. `None` if either isn't in the tree.
+    pub fn lca(&self, a: &Rc<T>, b: &Rc<T>) -> Option<Rc<T>> {
+        let (a, b) = (*self.index.get(a)?, *self.index.get(b)?);
+        Some(self.nodes[self.lca_index(a, b)].clone())
+    }
+
+    fn lca_index(&self, mut a: usize, mut b: usize) -> usize {
+        if self.depth[a] < self.depth[b] {
+            ::std::mem::swap(&mut a, &mut b);
+        }
+        let diff = self.depth[a] - self.depth[b];
+        for (k, level) in self.ancestors.iter().enumerate() {
+            if diff & (1 << k) != 0 {
+                a = level[a];
+            }
+        }
+        if a == b {
+            return a;
+        }
+        for level in self.ancestors.iter().rev() {
+            if level[a] != level[b] {
+                a = level[a];
+                b = level[b];
+            }
+        }
+        self.ancestors[0][a]
+    }
+
+    /// Number of edges on the tree path between `a` and `b`. `None` if either
+    /// isn't in the tree.
+    pub fn distance(&self, a: &Rc<T>, b: &Rc<T>) -> Option<usize> {
+        let (a, b) = (*self.index.get(a)?, *self.index.get(b)?);
+        let lca = self.lca_index(a, b);
+        Some(self.depth[a] + self.depth[b] - 2 * self.depth[lca])
+    }
+}
+
+/// Shortest distances between every pair of the vertices `0..n`, given a list of
+/// `(from, to, cost)` edges, using the Floyd-Warshall algorithm in `O(n^3)`.
+/// Unreachable pairs are `None`. Negative costs are allowed, a vertex on a
+/// negative cycle gets a negative distance to itself.
+pub fn floyd_warshall(n: usize, edges: &[(usize, usize, i64)]) -> Vec<Vec<Option<i64>>> {
+    floyd_warshall_paths(n, edges).distances
+}
+
+/// Same as `floyd_warshall`, but also keeping what is needed to reconstruct the
+/// shortest paths
+pub fn floyd_warshall_paths(n: usize, edges: &[(usize, usize, i64)]) -> AllPairsPaths {
+    let mut distances = vec![vec![None; n]; n];
+    let mut next = vec![vec![None; n]; n];
+    for i in 0..n {
+        distances[i][i] = Some(0);
+        next[i][i] = Some(i);
+    }
+    for &(from, to, cost) in edges {
+        match distances[from][to] {
+            Some(d) if d <= cost => {}
+            _ => {
+                distances[from][to] = Some(cost);
+                next[from][to] = Some(to);
+            }
+        }
+    }
+
+    for k in 0..n {
+        for i in 0..n {
+            let to_k = match distances[i][k] {
+                Some(d) => d,
+                None => continue,
+            };
+            for j in 0..n {
+                let through = match distances[k][j] {
+                    Some(d) => to_k + d,
+                    None => continue,
+                };
+                match distances[i][j] {
+                    Some(d) if d <= through => {}
+                    _ => {
+                        distances[i][j] = Some(through);
+                        next[i][j] = next[i][k];
+                    }
+                }
+            }
+        }
+    }
+    AllPairsPaths { distances, next }
+}
+
+/// All pairs shortest paths, from `floyd_warshall_paths`
+pub struct AllPairsPaths {
+    /// `distances[from][to]` is the cost of the shortest path, if any
+    pub distances: Vec<Vec<Option<i64>>>,
+    /* next[from][to] is the vertex after from on the shortest path */
+    next: Vec<Vec<Option<usize>>>,
+}
+
+impl AllPairsPaths {
+    pub fn distance(&self, from: usize, to: usize) -> Option<i64> {
+        self.distances[from][to]
+    }
+
+    /// The vertices of a shortest path from `from` to `to`, including both.
+    /// `None` if there is no path, or if it would involve a negative cycle.
+    pub fn path(&self, mut from: usize, to: usize) -> Option<Vec<usize>> {
+        self.next[from][to]?;
+        let mut path = vec![from];
+        while from != to {
+            match self.distances[from][from] {
+                Some(d) if d < 0 => return None,
+                _ => {}
+            }
+            from = self.next[from][to]?;
+            path.push(from);
+        }
+        Some(path)
+    }
+}
+
+/// Render the graph reachable from `start` in Graphviz DOT format, labeling
+/// vertices by their `Display` implementation. Repeated edges between the same
+/// pair of vertices are only drawn once.
+pub fn to_dot<T>(start: Rc<T>) -> String
+where
+    T: Vertex + Hash + Eq + Debug + fmt::Display,
+{
+    to_dot_with(start, |v| v.to_string())
+}
+
+/// Same as `to_dot`, but labeling vertices using `label`
+pub fn to_dot_with<T>(start: Rc<T>, label: impl Fn(&T) -> String) -> String
+where
+    T: Vertex + Hash + Eq + Debug,
+{
+    let mut index = HashMap::<Rc<T>, usize>::new();
+    let mut order = vec![start.clone()];
+    let mut edges = Vec::new();
+    let mut seen_edges = HashSet::new();
+    index.insert(start, 0);
+
+    let mut i = 0;
+    while i < order.len() {
+        for n in order[i].neighbors() {
+            let j = match index.get(&n) {
+                Some(&j) => j,
+                None => {
+                    index.insert(n.clone(), order.len());
+                    order.push(n);
+                    order.len() - 1
+                }
+            };
+            if seen_edges.insert((i, j)) {
+                edges.push((i, j));
+            }
+        }
+        i += 1;
+    }
+
+    let mut dot = String::from("digraph {\n");
+    for (i, v) in order.iter().enumerate() {
+        let text = label(v).replace('\\', "\\\\").replace('"', "\\\"");
+        dot.push_str(&format!("    {} [label=\"{}\"];\n", i, text));
+    }
+    for (i, j) in edges {
+        dot.push_str(&format!("    {} -> {};\n", i, j));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Graph given by an explicit list of directed edges, whose vertices are used
+/// through `AdjNode` handles implementing `Vertex`. Handles share a snapshot of
+/// the edges, so edges added after a handle was created aren't seen by it.
+#[derive(Clone, Debug, Default)]
+pub struct AdjGraph {
+    edges: Rc<Vec<Vec<(usize, usize)>>>,
+}
+
+impl AdjGraph {
+    pub fn new() -> AdjGraph {
+        AdjGraph {
+            edges: Rc::new(Vec::new()),
+        }
+    }
+
+    /// Add a vertex without edges, returning its id
+    pub fn add_node(&mut self) -> usize {
+        let edges = Rc::make_mut(&mut self.edges);
+        edges.push(Vec::new());
+        edges.len() - 1
+    }
+
+    /// Add a directed edge of unit cost, adding vertices as needed
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.add_weighted_edge(from, to, 1);
+    }
+
+    /// Add a directed edge with the given cost, adding vertices as needed
+    pub fn add_weighted_edge(&mut self, from: usize, to: usize, cost: usize) {
+        let edges = Rc::make_mut(&mut self.edges);
+        let size = from.max(to) + 1;
+        if edges.len() < size {
+            edges.resize(size, Vec::new());
+        }
+        edges[from].push((to, cost));
+    }
+
+    /// Handle to the vertex `id`, for use with the `Vertex` based searches
+    pub fn node(&self, id: usize) -> Rc<AdjNode> {
+        assert!(id < self.edges.len(), "Unknown vertex {}", id);
+        Rc::new(AdjNode {
+            id,
+            edges: self.edges.clone(),
+        })
+    }
+
+    /// Handles to all vertices, ordered by id
+    pub fn nodes(&self) -> Vec<Rc<AdjNode>> {
+        (0..self.edges.len()).map(|id| self.node(id)).collect()
+    }
+}
+
+/// A vertex of an `AdjGraph`. Handles are compared by id only, so handles from
+/// different graphs shouldn't be mixed.
+#[derive(Clone, Debug)]
+pub struct AdjNode {
+    id: usize,
+    edges: Rc<Vec<Vec<(usize, usize)>>>,
+}
+
+impl AdjNode {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl PartialEq for AdjNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for AdjNode {}
+
+impl Hash for AdjNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Vertex for AdjNode {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        self.edges[self.id]
+            .iter()
+            .map(|&(to, _)| {
+                Rc::new(AdjNode {
+                    id: to,
+                    edges: self.edges.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// 0 for the same vertex, otherwise 1, as nothing more is known
+    fn distance(&self, other: &Self) -> usize {
+        if self.id == other.id {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// The cheapest of the edges from `self` to `other`
+    fn edge_cost(&self, other: &Self) -> usize {
+        self.edges[self.id]
+            .iter()
+            .filter(|&&(to, _)| to == other.id)
+            .map(|&(_, cost)| cost)
+            .min()
+            .expect("Not a neighbor")
+    }
+}
+
+/// Builder for `Graph`, adding vertices and directed edges by index
+#[derive(Clone, Debug, Default)]
+pub struct GraphBuilder<V> {
+    vertices: Vec<V>,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl<V> GraphBuilder<V> {
+    pub fn new() -> GraphBuilder<V> {
+        GraphBuilder {
+            vertices: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add a vertex, returning its id
+    pub fn add_vertex(&mut self, vertex: V) -> usize {
+        self.vertices.push(vertex);
+        self.edges.push(Vec::new());
+        self.vertices.len() - 1
+    }
+
+    /// Add a directed edge of unit cost
+    pub fn add_edge(&mut self, from: usize, to: usize) -> &mut GraphBuilder<V> {
+        self.add_weighted_edge(from, to, 1)
+    }
+
+    /// Add a directed edge with the given cost. Panics if either id is unknown
+    pub fn add_weighted_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        cost: usize,
+    ) -> &mut GraphBuilder<V> {
+        assert!(to < self.vertices.len(), "Unknown vertex {}", to);
+        self.edges[from].push((to, cost));
+        self
+    }
+
+    pub fn build(self) -> Graph<V> {
+        Graph {
+            vertices: self.vertices,
+            edges: self.edges,
+        }
+    }
+}
+
+/// Graph storing vertices in a `Vec` and referring to them by index. Avoids the
+/// reference counting and hashing of the `Vertex` based searches, for when
+/// performance matters.
+#[derive(Clone, Debug)]
+pub struct Graph<V> {
+    vertices: Vec<V>,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl<V> Graph<V> {
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn vertex(&self, id: usize) -> &V {
+        &self.vertices[id]
+    }
+
+    /// Ids of the vertices reachable by a single edge from `id`
+    pub fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges[id].iter().map(|&(to, _)| to)
+    }
+
+    /// Number of edges from `start` to each vertex, or `None` if unreachable
+    pub fn bfs(&self, start: usize) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.len()];
+        let mut queue = VecDeque::new();
+        distances[start] = Some(0);
+        queue.push_back(start);
+
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[current].unwrap() + 1;
+            for next in self.neighbors(current) {
+                if distances[next].is_none() {
+                    distances[next] = Some(distance);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Number of edges from the closest of `sources` to each vertex, or `None`
+    /// if unreachable. Runs a BFS from each source on the `rayon` thread pool,
+    /// merging the results by taking the minimum distance.
+    #[cfg(feature = "parallel")]
+    pub fn bfs_distances_parallel(&self, sources: &[usize]) -> Vec<Option<usize>>
+    where
+        V: Sync,
+    {
+        sources.par_iter().map(|&source| self.bfs(source)).reduce(
+            || vec![None; self.len()],
+            |a, b| {
+                a.into_iter()
+                    .zip(b)
+                    .map(|pair| match pair {
+                        (Some(x), Some(y)) => Some(x.min(y)),
+                        (x, None) => x,
+                        (None, y) => y,
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Cheapest path from `start` to `goal` using Dijkstra's algorithm, along
+    /// with its total cost
+    pub fn dijkstra(&self, start: usize, goal: usize) -> Option<(Vec<usize>, usize)> {
+        let mut open = BinaryHeap::new();
+        let mut came_from = vec![None; self.len()];
+        let mut cost = vec![usize::MAX; self.len()];
+
+        open.push(Reverse((0, start)));
+        cost[start] = 0;
+
+        while let Some(Reverse((score, current))) = open.pop() {
+            if current == goal {
+                let mut path = vec![goal];
+                while let Some(prev) = came_from[*path.last().unwrap()] {
+                    path.push(prev);
+                }
+                path.reverse();
+                return Some((path, score));
+            }
+            if score > cost[current] {
+                continue;
+            }
+
+            for &(next, edge_cost) in &self.edges[current] {
+                let tentative_cost = score + edge_cost;
+                if tentative_cost < cost[next] {
+                    cost[next] = tentative_cost;
+                    came_from[next] = Some(current);
+                    open.push(Reverse((tentative_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct TestNode {
+    id: usize,
+    edges: Rc<Vec<Vec<(usize, i64)>>>,
+}
+
+#[cfg(test)]
+impl TestNode {
+    /// Build a directed graph of `n` nodes from `(from, to, cost)` edges
+    fn graph(n: usize, edges: &[(usize, usize, i64)]) -> Vec<Rc<TestNode>> {
+        let mut adjacency = vec![Vec::new(); n];
+        for &(from, to, cost) in edges {
+            adjacency[from].push((to, cost));
+        }
+        let adjacency = Rc::new(adjacency);
+        (0..n)
+            .map(|id| {
+                Rc::new(TestNode {
+                    id,
+                    edges: adjacency.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Build an undirected `width` x `height` grid, where `walls` are not connected
+    fn grid(width: usize, height: usize, walls: &[usize]) -> Vec<Rc<TestNode>> {
+        let mut edges = Vec::new();
+        for id in (0..width * height).filter(|id| !walls.contains(id)) {
+            if id % width + 1 < width && !walls.contains(&(id + 1)) {
+                edges.push((id, id + 1, 1));
+                edges.push((id + 1, id, 1));
+            }
+            if id + width < width * height && !walls.contains(&(id + width)) {
+                edges.push((id, id + width, 1));
+                edges.push((id + width, id, 1));
+            }
+        }
+        TestNode::graph(width * height, &edges)
+    }
+
+    fn ids(path: &[Rc<TestNode>]) -> Vec<usize> {
+        path.iter().map(|n| n.id).collect()
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for TestNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[cfg(test)]
+impl Eq for TestNode {}
+
+#[cfg(test)]
+impl Hash for TestNode {
+    fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+#[cfg(test)]
+impl Vertex for TestNode {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        self.edges[self.id]
+            .iter()
+            .map(|&(id, _)| {
+                Rc::new(TestNode {
+                    id,
+                    edges: self.edges.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn distance(&self, other: &Self) -> usize {
+        if self.id == other.id {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn edge_cost(&self, other: &Self) -> usize {
+        self.signed_edge_cost(other) as usize
+    }
+
+    fn signed_edge_cost(&self, other: &Self) -> i64 {
+        self.edges[self.id]
+            .iter()
+            .find(|&&(id, _)| id == other.id)
+            .map(|&(_, cost)| cost)
+            .unwrap()
+    }
+}
+
+/// A `TestNode` exposing its costs only through `neighbors_with_cost`, leaving
+/// `edge_cost` at the default unit cost
+#[cfg(test)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct CostOnlyNode(Rc<TestNode>);
+
+#[cfg(test)]
+impl CostOnlyNode {
+    fn graph(n: usize, edges: &[(usize, usize, i64)]) -> Vec<Rc<CostOnlyNode>> {
+        TestNode::graph(n, edges)
+            .into_iter()
+            .map(|node| Rc::new(CostOnlyNode(node)))
+            .collect()
+    }
+
+    fn ids(path: &[Rc<CostOnlyNode>]) -> Vec<usize> {
+        path.iter().map(|n| n.0.id).collect()
+    }
+}
+
+#[cfg(test)]
+impl Vertex for CostOnlyNode {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        self.neighbors_with_cost()
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect()
+    }
+
+    fn neighbors_with_cost(&self) -> Vec<(Rc<Self>, usize)> {
+        self.0
+            .neighbors()
+            .into_iter()
+            .map(|n| {
+                let cost = self.0.edge_cost(&n);
+                (Rc::new(CostOnlyNode(n)), cost)
+            })
+            .collect()
+    }
+
+    fn distance(&self, other: &Self) -> usize {
+        self.0.distance(&other.0)
+    }
+}
+
+#[test]
+fn test_reconstruct_path_order() {
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1)]);
+    let mut came_from = HashMap::new();
+    came_from.insert(nodes[1].clone(), nodes[0].clone());
+    came_from.insert(nodes[2].clone(), nodes[1].clone());
+    let path = reconstruct_path(nodes[2].clone(), &came_from);
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_path_iter() {
+    let nodes = TestNode::graph(4, &[]);
+    let mut came_from = HashMap::new();
+    for i in 1..4 {
+        came_from.insert(nodes[i].clone(), nodes[i - 1].clone());
+    }
+    let path = PathIter::new(nodes[3].clone(), &came_from).collect::<Vec<_>>();
+    assert_eq!(TestNode::ids(&path), vec![3, 2, 1, 0]);
+    let path = PathIter::new(nodes[3].clone(), &came_from)
+        .rev()
+        .collect::<Vec<_>>();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2, 3]);
+
+    let mut iter = PathIter::new(nodes[3].clone(), &came_from);
+    assert_eq!(iter.next().map(|v| v.id), Some(3));
+    assert_eq!(iter.next_back().map(|v| v.id), Some(0));
+    assert_eq!(iter.next().map(|v| v.id), Some(2));
+    assert_eq!(iter.next_back().map(|v| v.id), Some(1));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_dijkstra_search() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (0, 3, 5)]);
+    let (path, cost) = dijkstra_search(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2, 3]);
+    assert_eq!(cost, 3);
+
+    assert!(dijkstra_search(nodes[3].clone(), nodes[0].clone()).is_none());
+}
+
+#[test]
+fn test_search_masked() {
+    let nodes = TestNode::grid(3, 3, &[]);
+    let blocked = [1, 4]
+        .iter()
+        .map(|&i| nodes[i].clone())
+        .collect::<HashSet<_>>();
+    let path = astar_search_masked(nodes[0].clone(), nodes[2].clone(), &blocked).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 3, 6, 7, 8, 5, 2]);
+    let (path, cost) =
+        dijkstra_search_masked(nodes[0].clone(), nodes[2].clone(), &blocked).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 3, 6, 7, 8, 5, 2]);
+    assert_eq!(cost, 6);
+
+    /* The same graph without the mask */
+    assert_eq!(
+        astar_search(nodes[0].clone(), nodes[2].clone())
+            .unwrap()
+            .len(),
+        3
+    );
+
+    let mut blocked = blocked;
+    blocked.insert(nodes[7].clone());
+    assert!(astar_search_masked(nodes[0].clone(), nodes[2].clone(), &blocked).is_none());
+    assert!(dijkstra_search_masked(nodes[0].clone(), nodes[2].clone(), &blocked).is_none());
+
+    /* Blocked endpoints */
+    assert!(astar_search_masked(nodes[1].clone(), nodes[2].clone(), &blocked).is_none());
+    assert!(dijkstra_search_masked(nodes[0].clone(), nodes[4].clone(), &blocked).is_none());
+}
+
+#[test]
+fn test_neighbors_with_cost() {
+    /* Positions along a slope, where climbing costs the height gained */
+    #[derive(Debug, Hash, PartialEq, Eq)]
+    struct Slope {
+        position: usize,
+        heights: Rc<Vec<usize>>,
+    }
+
+    impl Vertex for Slope {
+        fn neighbors(&self) -> Vec<Rc<Self>> {
+            self.neighbors_with_cost()
+                .into_iter()
+                .map(|(n, _)| n)
+                .collect()
+        }
+
+        fn neighbors_with_cost(&self) -> Vec<(Rc<Self>, usize)> {
+            let mut positions = vec![self.position + 1];
+            if self.position > 0 {
+                positions.push(self.position - 1);
+            }
+            positions
+                .into_iter()
+                .filter(|&p| p < self.heights.len())
+                .map(|position| {
+                    let climb = self.heights[position].saturating_sub(self.heights[self.position]);
+                    let next = Slope {
+                        position,
+                        heights: self.heights.clone(),
+                    };
+                    (Rc::new(next), 1 + climb)
+                })
+                .collect()
+        }
+
+        fn distance(&self, other: &Self) -> usize {
+            (self.position as isize - other.position as isize).unsigned_abs()
+        }
+    }
+
+    let heights = Rc::new(vec![0, 3, 5, 9]);
+    let at = |position| {
+        Rc::new(Slope {
+            position,
+            heights: heights.clone(),
+        })
+    };
+    assert_eq!(dijkstra_search(at(0), at(3)).unwrap().1, 12);
+    assert_eq!(dijkstra_search(at(3), at(0)).unwrap().1, 3);
+    assert_eq!(astar_search_cost(at(0), at(3)).unwrap().1, 12);
+}
+
+#[test]
+fn test_astar_search_unreachable() {
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (3, 4, 1)]);
+    let explored = astar_search_explored(nodes[0].clone(), nodes[4].clone()).unwrap_err();
+    let mut ids = explored.iter().map(|n| n.id).collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec![0, 1, 2]);
+    let path = astar_search_explored(nodes[0].clone(), nodes[2].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2]);
+
+    let components = component_ids(&connected_components(&nodes));
+    assert_eq!(components[&nodes[0]], components[&nodes[2]]);
+    assert_ne!(components[&nodes[0]], components[&nodes[4]]);
+    assert!(astar_search_components(nodes[0].clone(), nodes[4].clone(), &components).is_none());
+    let path = astar_search_components(nodes[0].clone(), nodes[2].clone(), &components).unwrap();
+    assert_eq!(path.len(), 3);
+    /* Same component, but the edge only goes the other way */
+    assert!(astar_search_components(nodes[4].clone(), nodes[3].clone(), &components).is_none());
+}
+
+#[test]
+fn test_neighbors_with_cost_only() {
+    /*
+     * Only neighbors_with_cost knows the weights, so every search below has to
+     * read the costs from it. Under unit costs 0 -> 1 -> 3 and 0 -> 2 -> 3
+     * would tie.
+     */
+    let nodes = CostOnlyNode::graph(4, &[(0, 1, 5), (1, 3, 1), (0, 2, 1), (2, 3, 10), (1, 2, 1)]);
+    let paths = k_shortest_paths(nodes[0].clone(), nodes[3].clone(), 3)
+        .iter()
+        .map(|(p, c)| (CostOnlyNode::ids(p), *c))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            (vec![0, 1, 3], 6),
+            (vec![0, 2, 3], 11),
+            (vec![0, 1, 2, 3], 16)
+        ]
+    );
+
+    let (path, length) = longest_path_dag(&nodes).unwrap();
+    assert_eq!((CostOnlyNode::ids(&path), length), (vec![0, 1, 2, 3], 16));
+    let (path, cost) = shortest_hamiltonian_path(&nodes, false).unwrap();
+    assert_eq!((CostOnlyNode::ids(&path), cost), (vec![0, 1, 2, 3], 16));
+
+    let nodes = CostOnlyNode::graph(3, &[(0, 1, 0), (1, 2, 0), (0, 2, 1)]);
+    let (path, cost) = zero_one_bfs(nodes[0].clone(), nodes[2].clone()).unwrap();
+    assert_eq!((CostOnlyNode::ids(&path), cost), (vec![0, 1, 2], 0));
+}
+
+#[test]
+fn test_astar_search_cost() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (1, 3, 1)]);
+    let (path, cost) = astar_search_cost(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 3]);
+    assert_eq!(cost, 2);
+}
+
+#[test]
+fn test_bfs_search_all_cycle() {
+    let nodes = TestNode::graph(
+        4,
+        &[
+            (0, 1, 1),
+            (1, 0, 1),
+            (0, 2, 1),
+            (2, 1, 1),
+            (1, 3, 1),
+            (2, 3, 1),
+        ],
+    );
+    let paths = bfs_search_all(nodes[0].clone(), nodes[3].clone());
+    let paths = paths.iter().map(|p| TestNode::ids(p)).collect::<Vec<_>>();
+    assert_eq!(paths, vec![vec![0, 1, 3], vec![0, 2, 3], vec![0, 2, 1, 3]]);
+}
+
+#[test]
+fn test_dfs() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1), (3, 0, 1)]);
+    let mut order = Vec::new();
+    dfs(nodes[0].clone(), |n| order.push(n.id));
+    assert_eq!(order, vec![0, 1, 3, 2]);
+}
+
+#[test]
+fn test_leaves() {
+    /* 4 is shared by two subtrees */
+    let nodes = TestNode::graph(6, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (1, 4, 1), (2, 4, 1)]);
+    assert_eq!(TestNode::ids(&leaves(nodes[0].clone())), vec![3, 4]);
+    assert_eq!(TestNode::ids(&leaves(nodes[2].clone())), vec![4]);
+    assert_eq!(TestNode::ids(&leaves(nodes[5].clone())), vec![5]);
+}
+
+#[test]
+fn test_dfs_timed() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1), (3, 0, 1)]);
+    let events = ::std::cell::RefCell::new(Vec::new());
+    dfs_timed(
+        nodes[0].clone(),
+        |n| events.borrow_mut().push(("enter", n.id)),
+        |n| events.borrow_mut().push(("leave", n.id)),
+    );
+    assert_eq!(
+        events.into_inner(),
+        vec![
+            ("enter", 0),
+            ("enter", 1),
+            ("enter", 3),
+            ("leave", 3),
+            ("leave", 1),
+            ("enter", 2),
+            ("leave", 2),
+            ("leave", 0),
+        ]
+    );
+}
+
+#[test]
+fn test_topological_sort() {
+    let nodes = TestNode::graph(5, &[(0, 2, 1), (1, 2, 1), (2, 3, 1), (1, 4, 1), (4, 3, 1)]);
+    let order = topological_sort(&[nodes[0].clone(), nodes[1].clone()]).unwrap();
+    assert_eq!(TestNode::ids(&order), vec![1, 4, 0, 2, 3]);
+}
+
+#[test]
+fn test_topological_sort_cycle() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 1, 1)]);
+    let err = topological_sort(&nodes[..1]).unwrap_err();
+    assert_eq!(err.node.id, 1);
+}
+
+#[test]
+fn test_find_cycle() {
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 1, 1), (0, 4, 1)]);
+    let cycle = find_cycle(nodes[0].clone()).unwrap();
+    assert_eq!(TestNode::ids(&cycle), vec![1, 2, 3]);
+    assert!(has_cycle(nodes[0].clone()));
+
+    /* Diamond shape, shared vertex is not a cycle */
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1)]);
+    assert!(find_cycle(nodes[0].clone()).is_none());
+    assert!(!has_cycle(nodes[0].clone()));
+}
+
+#[test]
+fn test_count_paths() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1)]);
+    assert_eq!(count_paths(nodes[0].clone()).unwrap(), 2);
+
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1), (2, 0, 1)]);
+    assert_eq!(count_paths(nodes[0].clone()).unwrap_err().node.id, 0);
+}
+
+#[test]
+fn test_bellman_ford() {
+    let nodes = TestNode::graph(4, &[(0, 1, 4), (0, 2, 1), (2, 1, -2), (1, 3, 1)]);
+    let dist = bellman_ford(&nodes, nodes[0].clone()).unwrap();
+    assert_eq!(dist[&nodes[1]], -1);
+    assert_eq!(dist[&nodes[3]], 0);
+
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, -2), (2, 1, 1)]);
+    assert!(bellman_ford(&nodes, nodes[0].clone()).is_err());
+}
+
+#[test]
+fn test_bidirectional_bfs() {
+    /* 5x5 grid with a wall leaving a gap at the bottom */
+    let nodes = TestNode::grid(5, 5, &[2, 7, 12, 17]);
+    let path = bidirectional_bfs(nodes[0].clone(), nodes[4].clone()).unwrap();
+    let expected = astar_search(nodes[0].clone(), nodes[4].clone()).unwrap();
+    assert_eq!(path.len(), expected.len());
+    assert_eq!(path.first().unwrap().id, 0);
+    assert_eq!(path.last().unwrap().id, 4);
+    for pair in path.windows(2) {
+        assert!(pair[0].neighbors().contains(&pair[1]));
+    }
+
+    let nodes = TestNode::grid(5, 5, &[2, 7, 12, 17, 22]);
+    assert!(bidirectional_bfs(nodes[0].clone(), nodes[4].clone()).is_none());
+}
+
+#[test]
+fn test_astar_search_weighted_requeue() {
+    /*
+     * The cheap first edge to 1 leads to an expensive edge to the goal, so 3 is
+     * first queued through 1 and then improved through 2. Reaching 2 through 1
+     * is worse than directly, and must not queue 2 again.
+     */
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 4), (1, 2, 5), (1, 3, 10), (2, 3, 1)]);
+    let (path, cost) = astar_search_cost(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 2, 3]);
+    assert_eq!(cost, 5);
+
+    let (_, stats) = astar_search_with_stats(nodes[0].clone(), nodes[3].clone());
+    assert_eq!(stats.expanded, 4);
+    assert_eq!(stats.max_open, 2);
+    assert_eq!(stats.reopened, 1);
+}
+
+#[test]
+fn test_astar_search_with_stats() {
+    /* Reaching 3 through 2 is no improvement over 1, so 3 is only queued once */
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1)]);
+    let (path, stats) = astar_search_with_stats(nodes[0].clone(), nodes[3].clone());
+    assert_eq!(path.unwrap().len(), 3);
+    assert_eq!(stats.expanded, 4);
+    assert_eq!(stats.max_open, 2);
+    assert_eq!(stats.reopened, 0);
+}
+
+#[test]
+fn test_connected_components() {
+    let nodes = TestNode::graph(6, &[(0, 1, 1), (2, 1, 1), (3, 4, 1), (4, 3, 1)]);
+    let components = connected_components(&nodes);
+    let components = components
+        .iter()
+        .map(|c| TestNode::ids(c))
+        .collect::<Vec<_>>();
+    assert_eq!(components, vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+}
+
+#[test]
+fn test_prim_mst() {
+    let mut edges = Vec::new();
+    for &(a, b, cost) in &[
+        (0, 1, 4),
+        (0, 2, 1),
+        (1, 2, 2),
+        (2, 3, 5),
+        (1, 3, 3),
+        (4, 5, 7),
+    ] {
+        edges.push((a, b, cost));
+        edges.push((b, a, cost));
+    }
+    let n = TestNode::graph(6, &edges);
+    let tree = prim_mst(n[0].clone());
+    assert_eq!(
+        tree.iter()
+            .map(|(a, b, w)| (a.id, b.id, *w))
+            .collect::<Vec<_>>(),
+        vec![(0, 2, 1), (2, 1, 2), (1, 3, 3)]
+    );
+
+    /* Only the component of the start is spanned */
+    let tree = prim_mst(n[4].clone());
+    assert_eq!(tree.len(), 1);
+    assert_eq!((tree[0].0.id, tree[0].1.id, tree[0].2), (4, 5, 7));
+    assert!(prim_mst(TestNode::graph(1, &[])[0].clone()).is_empty());
+}
+
+#[test]
+fn test_kruskal() {
+    let n = TestNode::graph(6, &[]);
+    let edges = vec![
+        (n[0].clone(), n[1].clone(), 4),
+        (n[0].clone(), n[2].clone(), 1),
+        (n[1].clone(), n[2].clone(), 2),
+        (n[2].clone(), n[3].clone(), 5),
+        (n[1].clone(), n[3].clone(), 3),
+        (n[4].clone(), n[5].clone(), 7),
+    ];
+    let (tree, total) = kruskal(&edges);
+    assert_eq!(total, 13);
+    assert_eq!(
+        tree.iter()
+            .map(|(a, b, _)| (a.id, b.id))
+            .collect::<Vec<_>>(),
+        vec![(0, 2), (1, 2), (1, 3), (4, 5)]
+    );
+}
+
+#[test]
+fn test_bfs_distances() {
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (0, 2, 1), (2, 3, 1), (3, 0, 1)]);
+    let dist = bfs_distances(nodes[0].clone());
+    assert_eq!(dist.len(), 4);
+    assert_eq!(dist[&nodes[0]], 0);
+    assert_eq!(dist[&nodes[1]], 1);
+    assert_eq!(dist[&nodes[2]], 1);
+    assert_eq!(dist[&nodes[3]], 2);
+}
+
+#[test]
+fn test_multi_source_bfs() {
+    /* Chain 0 - 1 - 2 - 3 - 4 with sources at both ends */
+    let nodes = TestNode::grid(5, 1, &[]);
+    let starts = [nodes[0].clone(), nodes[4].clone()];
+    let dist = multi_source_bfs(&starts);
+    assert_eq!(
+        nodes.iter().map(|n| dist[n]).collect::<Vec<_>>(),
+        vec![0, 1, 2, 1, 0]
+    );
+
+    let nearest = multi_source_bfs_nearest(&starts);
+    assert_eq!(
+        nodes.iter().map(|n| nearest[n].1).collect::<Vec<_>>(),
+        vec![0, 0, 0, 1, 1]
+    );
+}
+
+#[test]
+fn test_zero_one_bfs() {
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 4, 1), (0, 2, 0), (2, 3, 0), (3, 4, 1)]);
+    let (path, cost) = zero_one_bfs(nodes[0].clone(), nodes[4].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 2, 3, 4]);
+    assert_eq!(cost, 1);
+    assert!(zero_one_bfs(nodes[4].clone(), nodes[0].clone()).is_none());
+}
+
+#[test]
+fn test_k_shortest_paths() {
+    let nodes = TestNode::graph(
+        6,
+        &[
+            (0, 1, 3),
+            (0, 2, 2),
+            (1, 3, 4),
+            (2, 1, 1),
+            (2, 3, 2),
+            (2, 4, 3),
+            (3, 4, 2),
+            (3, 5, 1),
+            (4, 5, 2),
+        ],
+    );
+    let paths = k_shortest_paths(nodes[0].clone(), nodes[5].clone(), 3);
+    let paths = paths
+        .iter()
+        .map(|(p, c)| (TestNode::ids(p), *c))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            (vec![0, 2, 3, 5], 5),
+            (vec![0, 2, 4, 5], 7),
+            (vec![0, 1, 3, 5], 8),
+        ]
+    );
+
+    let paths = k_shortest_paths(nodes[0].clone(), nodes[5].clone(), 100);
+    assert_eq!(paths.len(), 7);
+    assert!(paths.windows(2).all(|w| w[0].1 <= w[1].1));
+}
+
+#[test]
+fn test_longest_path_dag() {
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (0, 2, 5), (2, 3, 1), (4, 3, 1)]);
+    let (path, length) = longest_path_dag(&[nodes[0].clone(), nodes[4].clone()]).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 2, 3]);
+    assert_eq!(length, 6);
+
+    let nodes = TestNode::graph(2, &[(0, 1, 1), (1, 0, 1)]);
+    assert!(longest_path_dag(&nodes).is_none());
+}
+
+#[test]
+fn test_astar_search_with() {
+    let nodes = TestNode::grid(4, 4, &[1, 5, 9]);
+    let path = astar_search_with(nodes[0].clone(), nodes[3].clone(), |_, _| 0).unwrap();
+    assert_eq!(path.len(), 10);
+
+    /* Manhattan distance on the grid is admissible and finds an equally short path */
+    let manhattan = |a: &TestNode, b: &TestNode| {
+        let dx = (a.id % 4) as i32 - (b.id % 4) as i32;
+        let dy = (a.id / 4) as i32 - (b.id / 4) as i32;
+        (dx.abs() + dy.abs()) as usize
+    };
+    let path = astar_search_with(nodes[0].clone(), nodes[3].clone(), manhattan).unwrap();
+    assert_eq!(path.len(), 10);
+}
+
+#[test]
+fn test_astar_search_multi() {
+    let nodes = TestNode::grid(5, 1, &[]);
+    let goals = [nodes[0].clone(), nodes[4].clone()]
+        .iter()
+        .cloned()
+        .collect::<HashSet<_>>();
+    let path = astar_search_multi(nodes[3].clone(), &goals).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![3, 4]);
+    assert!(astar_search_multi(nodes[3].clone(), &HashSet::new()).is_none());
+}
+
+#[test]
+fn test_graph() {
+    let mut builder = GraphBuilder::new();
+    let ids = "abcde"
+        .chars()
+        .map(|c| builder.add_vertex(c))
+        .collect::<Vec<_>>();
+    builder
+        .add_weighted_edge(ids[0], ids[1], 4)
+        .add_edge(ids[0], ids[2])
+        .add_edge(ids[2], ids[1])
+        .add_weighted_edge(ids[1], ids[3], 2);
+    let graph = builder.build();
+
+    assert_eq!(graph.len(), 5);
+    assert_eq!(*graph.vertex(ids[2]), 'c');
+    assert_eq!(
+        graph.bfs(ids[0]),
+        vec![Some(0), Some(1), Some(1), Some(2), None]
+    );
+    assert_eq!(
+        graph.dijkstra(ids[0], ids[3]),
+        Some((vec![ids[0], ids[2], ids[1], ids[3]], 4))
+    );
+    assert_eq!(graph.dijkstra(ids[0], ids[4]), None);
+}
+
+#[test]
+fn test_articulation_points_and_bridges() {
+    /* A triangle 0-1-2 with a tail 2-3-4 */
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (2, 0, 1), (2, 3, 1), (3, 4, 1)]);
+    let points = articulation_points(&nodes[..1]);
+    let mut ids = points.iter().map(|v| v.id).collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec![2, 3]);
+    let found = bridges(&nodes[..1])
+        .iter()
+        .map(|(a, b)| (a.id, b.id))
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![(3, 4), (2, 3)]);
+
+    /* Starting in the middle of the tail, the root has two DFS children */
+    let points = articulation_points(&[nodes[3].clone(), nodes[0].clone()]);
+    assert!(points.contains(&nodes[3]));
+    assert!(!points.contains(&nodes[4]));
+
+    /* A cycle has neither */
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1), (2, 0, 1)]);
+    assert!(articulation_points(&nodes).is_empty());
+    assert!(bridges(&nodes).is_empty());
+}
+
+#[test]
+fn test_strongly_connected_components() {
+    /* Cycles 0-1-2 and 3-4, with an edge from the first to the second */
+    let nodes = TestNode::graph(
+        6,
+        &[
+            (0, 1, 1),
+            (1, 2, 1),
+            (2, 0, 1),
+            (2, 3, 1),
+            (3, 4, 1),
+            (4, 3, 1),
+            (4, 5, 1),
+        ],
+    );
+    let components = strongly_connected_components(&nodes[..1])
+        .iter()
+        .map(|c| {
+            let mut ids = TestNode::ids(c);
+            ids.sort();
+            ids
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(components, vec![vec![5], vec![3, 4], vec![0, 1, 2]]);
+}
+
+#[test]
+fn test_grid_astar() {
+    /* A wall at x = 2 with a gap at y = 3 */
+    let passable =
+        |p: &Cartesian| p.x >= 0 && p.y >= 0 && p.x < 5 && p.y < 5 && (p.x != 2 || p.y == 3);
+    let path = grid_astar(Cartesian::new(0, 0), Cartesian::new(4, 0), passable, false).unwrap();
+    assert_eq!(path.len(), 11);
+    assert_eq!(path.first(), Some(&Cartesian::new(0, 0)));
+    assert_eq!(path.last(), Some(&Cartesian::new(4, 0)));
+    assert!(path.contains(&Cartesian::new(2, 3)));
+
+    let path = grid_astar(Cartesian::new(0, 0), Cartesian::new(4, 0), passable, true).unwrap();
+    assert_eq!(path.len(), 7);
+    assert!(grid_astar(Cartesian::new(0, 0), Cartesian::new(9, 9), passable, true).is_none());
+}
+
+#[test]
+fn test_search_with() {
+    /* Reach a number from 1 by adding one or doubling, staying at most 20 */
+    let neighbors = |&n: &u32| {
+        vec![n + 1, n * 2]
+            .into_iter()
+            .filter(|&m| m <= 20)
+            .collect()
+    };
+    let path = search_with(1, 10, neighbors, |_, _| 0).unwrap();
+    assert_eq!(path, vec![1, 2, 4, 5, 10]);
+    assert_eq!(search_with(1, 0, neighbors, |_, _| 0), None);
+
+    /* Same result with an admissible heuristic */
+    let path = search_with(1, 20, neighbors, |&n, &goal| (n < goal) as usize).unwrap();
+    assert_eq!(path.len(), 6);
+}
+
+#[test]
+fn test_tree_lca() {
+    /*
+     *        0
+     *       / \
+     *      1   2
+     *     / \   \
+     *    3   4   5
+     *    |
+     *    6
+     */
+    let nodes = TestNode::graph(
+        8,
+        &[
+            (0, 1, 1),
+            (0, 2, 1),
+            (1, 3, 1),
+            (1, 4, 1),
+            (2, 5, 1),
+            (3, 6, 1),
+        ],
+    );
+    let tree = TreeLca::new(nodes[0].clone());
+    let lca = |a: usize, b: usize| tree.lca(&nodes[a], &nodes[b]).map(|v| v.id);
+    assert_eq!(lca(6, 4), Some(1));
+    assert_eq!(lca(6, 5), Some(0));
+    assert_eq!(lca(3, 6), Some(3));
+    assert_eq!(lca(2, 2), Some(2));
+    assert_eq!(lca(6, 7), None);
+    assert_eq!(tree.distance(&nodes[6], &nodes[5]), Some(5));
+    assert_eq!(tree.distance(&nodes[4], &nodes[4]), Some(0));
+}
+
+#[test]
+fn test_diameter() {
+    let nodes = TestNode::grid(4, 3, &[]);
+    assert_eq!(eccentricity(nodes[0].clone()), 5);
+    assert_eq!(eccentricity(nodes[5].clone()), 3);
+    assert_eq!(diameter(&nodes[..1]), 5);
+
+    let nodes = TestNode::graph(3, &[(0, 1, 1)]);
+    assert_eq!(diameter(&nodes[1..]), 0);
+    assert_eq!(diameter(&nodes), 1);
+}
+
+#[test]
+fn test_greedy_coloring() {
+    /* A 5-cycle needs three colors */
+    let nodes = TestNode::graph(5, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 4, 1), (4, 0, 1)]);
+    let coloring = greedy_coloring(&nodes[..1]);
+    assert_eq!(coloring.len(), 5);
+    assert_eq!(color_count(&coloring), 3);
+    for i in 0..5 {
+        assert_ne!(coloring[&nodes[i]], coloring[&nodes[(i + 1) % 5]]);
+    }
+
+    let nodes = TestNode::grid(3, 3, &[]);
+    assert_eq!(color_count(&greedy_coloring(&nodes[..1])), 2);
+}
+
+#[test]
+fn test_iddfs() {
+    let nodes = TestNode::grid(4, 4, &[5, 6, 9]);
+    let path = iddfs(nodes[0].clone(), nodes[10].clone(), 10).unwrap();
+    assert_eq!(path.len(), 7);
+    assert_eq!(path.first(), Some(&nodes[0]));
+    assert_eq!(path.last(), Some(&nodes[10]));
+    assert!(iddfs(nodes[0].clone(), nodes[10].clone(), 5).is_none());
+    assert_eq!(
+        TestNode::ids(&iddfs(nodes[3].clone(), nodes[3].clone(), 0).unwrap()),
+        vec![3]
+    );
+
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 0, 1)]);
+    assert!(iddfs(nodes[0].clone(), nodes[2].clone(), 100).is_none());
+}
+
+#[test]
+fn test_astar_search_bounded() {
+    let nodes = TestNode::grid(3, 1, &[]);
+    let path = astar_search_bounded(nodes[0].clone(), nodes[2].clone(), 3).unwrap();
+    assert_eq!(TestNode::ids(&path.unwrap()), vec![0, 1, 2]);
+    assert_eq!(
+        astar_search_bounded(nodes[0].clone(), nodes[2].clone(), 2),
+        Err(SearchLimitExceeded)
+    );
+
+    /* Running out of vertices is not exceeding the limit */
+    let nodes = TestNode::graph(3, &[(0, 1, 1)]);
+    assert_eq!(
+        astar_search_bounded(nodes[0].clone(), nodes[2].clone(), 2),
+        Ok(None)
+    );
+}
+
+#[test]
+fn test_count_simple_paths() {
+    /* A 2x2 grid of cells has two ways around, a 3x2 grid four */
+    let nodes = TestNode::grid(2, 2, &[]);
+    assert_eq!(
+        count_simple_paths(nodes[0].clone(), nodes[3].clone(), None),
+        2
+    );
+    let nodes = TestNode::grid(3, 2, &[]);
+    assert_eq!(
+        count_simple_paths(nodes[0].clone(), nodes[5].clone(), None),
+        4
+    );
+    assert_eq!(
+        count_simple_paths(nodes[0].clone(), nodes[5].clone(), Some(3)),
+        3
+    );
+    assert_eq!(
+        count_simple_paths(nodes[0].clone(), nodes[5].clone(), Some(2)),
+        0
+    );
+    assert_eq!(
+        count_simple_paths(nodes[0].clone(), nodes[0].clone(), None),
+        1
+    );
+}
+
+#[test]
+fn test_shortest_hamiltonian_path() {
+    let mut edges = Vec::new();
+    for &(a, b, cost) in &[
+        (0, 1, 1),
+        (0, 2, 4),
+        (0, 3, 2),
+        (1, 2, 2),
+        (1, 3, 5),
+        (2, 3, 3),
+    ] {
+        edges.push((a, b, cost));
+        edges.push((b, a, cost));
+    }
+    let nodes = TestNode::graph(4, &edges);
+
+    let (path, cost) = shortest_hamiltonian_path(&nodes, false).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2, 3]);
+    assert_eq!(cost, 6);
+
+    let (path, cost) = shortest_hamiltonian_path(&nodes, true).unwrap();
+    assert_eq!(path.len(), 5);
+    assert_eq!(cost, 8);
+
+    /* Without the edge back to the start there is no tour */
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1)]);
+    assert_eq!(shortest_hamiltonian_path(&nodes, false).unwrap().1, 2);
+    assert!(shortest_hamiltonian_path(&nodes, true).is_none());
+    assert_eq!(
+        shortest_hamiltonian_path(&nodes[..1], true).map(|(p, c)| (TestNode::ids(&p), c)),
+        Some((vec![0], 0))
+    );
+}
+
+#[test]
+fn test_bfs_shortest_path() {
+    /*
+     * S . . #
+     * # # . #
+     * . . . G
+     */
+    let nodes = TestNode::grid(4, 3, &[3, 4, 5, 7]);
+    let path = bfs_shortest_path(nodes[0].clone(), nodes[11].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2, 6, 10, 11]);
+    assert_eq!(
+        TestNode::ids(&bfs_shortest_path(nodes[8].clone(), nodes[8].clone()).unwrap()),
+        vec![8]
+    );
+    assert!(bfs_shortest_path(nodes[0].clone(), nodes[3].clone()).is_none());
+}
+
+#[test]
+fn test_to_dot() {
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (0, 2, 1), (1, 2, 1), (2, 0, 1), (0, 1, 5)]);
+    let dot = to_dot_with(nodes[0].clone(), |v| format!("n\"{}", v.id));
+    assert_eq!(
+        dot,
+        "digraph {\n    0 [label=\"n\\\"0\"];\n    1 [label=\"n\\\"1\"];\n    \
+         2 [label=\"n\\\"2\"];\n    0 -> 1;\n    0 -> 2;\n    1 -> 2;\n    2 -> 0;\n}\n"
+    );
+}
+
+#[test]
+fn test_adj_graph() {
+    let mut graph = AdjGraph::new();
+    let start = graph.add_node();
+    graph.add_edge(start, 1);
+    graph.add_weighted_edge(start, 2, 5);
+    graph.add_edge(1, 3);
+    graph.add_weighted_edge(2, 3, 1);
+    graph.add_weighted_edge(start, 2, 2);
+
+    let ids = |path: &[Rc<AdjNode>]| path.iter().map(|v| v.id()).collect::<Vec<_>>();
+    let path = bfs_shortest_path(graph.node(start), graph.node(3)).unwrap();
+    assert_eq!(ids(&path), vec![0, 1, 3]);
+    let (path, cost) = dijkstra_search(graph.node(start), graph.node(3)).unwrap();
+    assert_eq!((ids(&path), cost), (vec![0, 1, 3], 2));
+    assert_eq!(graph.node(start).edge_cost(&graph.node(2)), 2);
+    assert_eq!(graph.nodes().len(), 4);
+
+    /* Existing handles keep seeing the edges they were created with */
+    let old = graph.node(3);
+    graph.add_edge(3, 0);
+    assert!(old.neighbors().is_empty());
+    assert_eq!(graph.node(3).neighbors(), vec![graph.node(0)]);
+}
+
+#[test]
+fn test_astar_search_edge_cost() {
+    /* The direct edge is one hop but costs more than going around */
+    let nodes = TestNode::graph(4, &[(0, 3, 10), (0, 1, 2), (1, 2, 2), (2, 3, 2)]);
+    let (path, cost) = astar_search_cost(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 2, 3]);
+    assert_eq!(cost, 6);
+}
+
+#[test]
+fn test_all_shortest_paths() {
+    /* 3x3 grid without walls, 6 shortest paths between opposite corners */
+    let nodes = TestNode::grid(3, 3, &[]);
+    let paths = all_shortest_paths(nodes[0].clone(), nodes[8].clone());
+    assert_eq!(paths.len(), 6);
+    assert!(paths
+        .iter()
+        .all(|p| p.len() == 5 && p[0] == nodes[0] && p[4] == nodes[8]));
+    let unique = paths
+        .iter()
+        .map(|p| TestNode::ids(p))
+        .collect::<HashSet<_>>();
+    assert_eq!(unique.len(), 6);
+
+    /* With the center blocked only the two ways around remain */
+    let nodes = TestNode::grid(3, 3, &[4]);
+    assert_eq!(
+        all_shortest_paths(nodes[0].clone(), nodes[8].clone()).len(),
+        2
+    );
+    assert!(all_shortest_paths(nodes[0].clone(), nodes[4].clone()).is_empty());
+    assert_eq!(
+        all_shortest_paths(nodes[0].clone(), nodes[0].clone()),
+        vec![vec![nodes[0].clone()]]
+    );
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_random_walk() {
+    use rand::{SeedableRng, XorShiftRng};
+
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    let nodes = TestNode::grid(3, 3, &[]);
+    let walk = random_walk(nodes[4].clone(), 20, &mut rng);
+    assert_eq!(walk.len(), 21);
+    assert_eq!(walk[0], nodes[4]);
+    for pair in walk.windows(2) {
+        assert!(pair[0].neighbors().contains(&pair[1]));
+    }
+
+    /* Stops at the dead end */
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1)]);
+    let walk = random_walk(nodes[0].clone(), 10, &mut rng);
+    assert_eq!(TestNode::ids(&walk), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_degrees() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 2, 1), (2, 0, 1)]);
+    let out = out_degrees(&nodes[..1]);
+    assert_eq!(out.len(), 3);
+    assert_eq!((out[&nodes[0]], out[&nodes[1]], out[&nodes[2]]), (2, 1, 1));
+    let degrees = in_degrees(&nodes[..1]);
+    assert_eq!(degrees.len(), 3);
+    assert_eq!(
+        (degrees[&nodes[0]], degrees[&nodes[1]], degrees[&nodes[2]]),
+        (1, 1, 2)
+    );
+
+    let nodes = TestNode::graph(2, &[(0, 1, 1)]);
+    let degrees = in_degrees(&nodes[..1]);
+    assert_eq!((degrees[&nodes[0]], degrees[&nodes[1]]), (0, 1));
+}
+
+#[test]
+fn test_reverse_adjacency() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 2, 1)]);
+    let reverse = reverse_adjacency(&nodes[..1]);
+    assert_eq!(reverse.len(), 3);
+    assert!(reverse[&nodes[0]].is_empty());
+    assert_eq!(TestNode::ids(&reverse[&nodes[1]]), vec![0]);
+    let mut into_2 = TestNode::ids(&reverse[&nodes[2]]);
+    into_2.sort();
+    assert_eq!(into_2, vec![0, 1]);
+}
+
+#[test]
+fn test_search_tie_break() {
+    /* Two equally cheap paths, the one through the first pushed vertex wins */
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (0, 2, 1), (1, 3, 1), (2, 3, 1)]);
+    let (path, _) = dijkstra_search(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 3]);
+    let path = astar_search(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 1, 3]);
+
+    let nodes = TestNode::graph(4, &[(0, 2, 1), (0, 1, 1), (1, 3, 1), (2, 3, 1)]);
+    let (path, _) = dijkstra_search(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 2, 3]);
+    let path = astar_search(nodes[0].clone(), nodes[3].clone()).unwrap();
+    assert_eq!(TestNode::ids(&path), vec![0, 2, 3]);
+}
+
+#[test]
+fn test_floyd_warshall() {
+    let edges = [(0, 1, 4), (0, 2, 1), (2, 1, 2), (1, 3, 1), (3, 0, -2)];
+    let distances = floyd_warshall(5, &edges);
+    assert_eq!(distances[0][1], Some(3));
+    assert_eq!(distances[0][3], Some(4));
+    assert_eq!(distances[3][1], Some(1));
+    assert_eq!(distances[0][4], None);
+    assert_eq!(distances[4][4], Some(0));
+
+    let paths = floyd_warshall_paths(5, &edges);
+    assert_eq!(paths.path(0, 3), Some(vec![0, 2, 1, 3]));
+    assert_eq!(paths.path(2, 2), Some(vec![2]));
+    assert_eq!(paths.path(0, 4), None);
+
+    /* 0 -> 1 -> 0 costs -1 */
+    let distances = floyd_warshall(2, &[(0, 1, 1), (1, 0, -2)]);
+    assert!(distances[0][0].unwrap() < 0);
+    assert!(floyd_warshall_paths(2, &[(0, 1, 1), (1, 0, -2)])
+        .path(0, 1)
+        .is_none());
+}
+
+#[test]
+fn test_two_coloring() {
+    let nodes = TestNode::graph(4, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 0, 1)]);
+    let coloring = two_coloring(&nodes[..1]).unwrap();
+    assert_eq!(coloring.len(), 4);
+    assert_eq!(coloring[&nodes[0]], coloring[&nodes[2]]);
+    assert_eq!(coloring[&nodes[1]], coloring[&nodes[3]]);
+    assert_ne!(coloring[&nodes[0]], coloring[&nodes[1]]);
+
+    let nodes = TestNode::graph(3, &[(0, 1, 1), (1, 2, 1), (2, 0, 1)]);
+    assert!(two_coloring(&nodes).is_none());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_bfs_distances_parallel() {
+    let mut builder = GraphBuilder::new();
+    for i in 0..6 {
+        builder.add_vertex(i);
+    }
+    for i in 0..4 {
+        builder.add_edge(i, i + 1).add_edge(i + 1, i);
     }
+    let graph = builder.build();
+    assert_eq!(
+        graph.bfs_distances_parallel(&[0, 4]),
+        vec![Some(0), Some(1), Some(2), Some(1), Some(0), None]
+    );
+    assert_eq!(graph.bfs_distances_parallel(&[2]), graph.bfs(2));
+    assert_eq!(graph.bfs_distances_parallel(&[]), vec![None; 6]);
 }
@@ -0,0 +1,327 @@
+use coord::Cartesian;
+use graph::Vertex;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// A rectangular grid of cells, stored row by row in a flat `Vec`.
+/// `(0, 0)` is the top left cell, x grows to the right and y grows downwards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Create a grid from `cells` given row by row
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Grid<T> {
+        assert_eq!(cells.len(), width * height, "Cell count doesn't match size");
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parse a newline separated character map, converting each character
+    /// using `f`. Trailing empty lines are ignored. Panics if the lines are
+    /// not all of the same length.
+    pub fn from_str_with(s: &str, f: impl Fn(char) -> T) -> Grid<T> {
+        let lines = s.trim_end_matches('\n').lines().collect::<Vec<_>>();
+        let width = lines.first().map_or(0, |l| l.chars().count());
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for line in &lines {
+            assert_eq!(line.chars().count(), width, "Grid lines differ in length");
+            cells.extend(line.chars().map(&f));
+        }
+        Grid::new(width, lines.len(), cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Check if `c` lies within the grid
+    pub fn contains(&self, c: &Cartesian) -> bool {
+        c.x >= 0 && c.y >= 0 && (c.x as usize) < self.width && (c.y as usize) < self.height
+    }
+
+    fn index(&self, c: &Cartesian) -> Option<usize> {
+        if self.contains(c) {
+            Some(c.y as usize * self.width + c.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, c: &Cartesian) -> Option<&T> {
+        self.index(c).map(move |i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, c: &Cartesian) -> Option<&mut T> {
+        match self.index(c) {
+            Some(i) => Some(&mut self.cells[i]),
+            None => None,
+        }
+    }
+
+    /// Points around `c` excluding diagonal, that are within the grid
+    pub fn neigh4(&self, c: &Cartesian) -> Vec<Cartesian> {
+        c.neigh4_where(|n| self.contains(n)).collect()
+    }
+
+    /// Points around `c` including diagonal, that are within the grid
+    pub fn neigh8(&self, c: &Cartesian) -> Vec<Cartesian> {
+        c.neigh8_where(|n| self.contains(n)).collect()
+    }
+
+    /// Replace every cell 4-connected to `start` whose value satisfies
+    /// `predicate` with `fill`. Returns the number of filled cells, which is 0
+    /// if `start` itself doesn't satisfy the predicate.
+    pub fn flood_fill(
+        &mut self,
+        start: &Cartesian,
+        predicate: impl Fn(&T) -> bool,
+        fill: T,
+    ) -> usize
+    where
+        T: Clone,
+    {
+        let mut visited = vec![false; self.cells.len()];
+        let mut stack = vec![*start];
+        let mut filled = 0;
+
+        while let Some(c) = stack.pop() {
+            let i = match self.index(&c) {
+                Some(i) if !visited[i] && predicate(&self.cells[i]) => i,
+                _ => continue,
+            };
+            visited[i] = true;
+            self.cells[i] = fill.clone();
+            filled += 1;
+            stack.extend(c.neigh4());
+        }
+        filled
+    }
+
+    /// Build a `width` x `height` grid where each cell is copied from the cell of
+    /// `self` at the position given by `source(x, y)`
+    fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x, y);
+                cells.push(self.cells[sy * self.width + sx].clone());
+            }
+        }
+        Grid::new(width, height, cells)
+    }
+
+    /// The grid rotated a quarter turn clockwise, swapping width and height
+    pub fn rotate_cw(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.height, self.width, |x, y| (y, self.height - 1 - x))
+    }
+
+    /// The grid rotated a quarter turn counter clockwise, swapping width and height
+    pub fn rotate_ccw(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.height, self.width, |x, y| (self.width - 1 - y, x))
+    }
+
+    /// The grid mirrored left to right
+    pub fn flip_horizontal(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.width, self.height, |x, y| (self.width - 1 - x, y))
+    }
+
+    /// The grid mirrored top to bottom
+    pub fn flip_vertical(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.width, self.height, |x, y| (x, self.height - 1 - y))
+    }
+
+    /// The grid mirrored over the diagonal from the top left, swapping width
+    /// and height
+    pub fn transpose(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.height, self.width, |x, y| (y, x))
+    }
+
+    /// All eight rotations and reflections of the grid, starting with the grid
+    /// itself followed by its clockwise rotations, and then the same for the
+    /// horizontally flipped grid
+    pub fn orientations(&self) -> Vec<Grid<T>>
+    where
+        T: Clone,
+    {
+        let mut orientations = Vec::with_capacity(8);
+        for grid in [self.clone(), self.flip_horizontal()].iter() {
+            let mut grid = grid.clone();
+            for _ in 0..4 {
+                let next = grid.rotate_cw();
+                orientations.push(grid);
+                grid = next;
+            }
+        }
+        orientations
+    }
+}
+
+/// A cell of a grid of open (`true`) and blocked (`false`) cells, implementing
+/// `Vertex` for the searches in `graph`. Moves are to the orthogonally adjacent
+/// open cells at unit cost, and the heuristic `distance` is the manhattan
+/// distance. As each move changes the manhattan distance by exactly one it
+/// never overestimates, so A* finds shortest paths.
+#[derive(Clone, Debug)]
+pub struct GridCell {
+    grid: Rc<Grid<bool>>,
+    position: Cartesian,
+}
+
+impl GridCell {
+    pub fn new(grid: Rc<Grid<bool>>, position: Cartesian) -> GridCell {
+        GridCell { grid, position }
+    }
+
+    pub fn position(&self) -> Cartesian {
+        self.position
+    }
+}
+
+impl Vertex for GridCell {
+    fn neighbors(&self) -> Vec<Rc<Self>> {
+        self.grid
+            .neigh4(&self.position)
+            .into_iter()
+            .filter(|p| self.grid.get(p) == Some(&true))
+            .map(|p| Rc::new(GridCell::new(self.grid.clone(), p)))
+            .collect()
+    }
+
+    fn distance(&self, other: &Self) -> usize {
+        self.position.manhattan_distance(&other.position)
+    }
+}
+
+/// Cells are compared by position only, so cells of different grids shouldn't
+/// be mixed
+impl PartialEq for GridCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+    }
+}
+
+impl Eq for GridCell {}
+
+impl Hash for GridCell {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.position.hash(state);
+    }
+}
+
+#[test]
+fn test_from_str_with() {
+    let grid = Grid::from_str_with("#..\n.#.\n", |c| c == '#');
+    assert_eq!(grid.width(), 3);
+    assert_eq!(grid.height(), 2);
+    assert_eq!(grid.get(&Cartesian::new(0, 0)), Some(&true));
+    assert_eq!(grid.get(&Cartesian::new(1, 1)), Some(&true));
+    assert_eq!(grid.get(&Cartesian::new(2, 1)), Some(&false));
+    assert_eq!(grid.get(&Cartesian::new(3, 0)), None);
+    assert_eq!(grid.get(&Cartesian::new(0, -1)), None);
+    assert_eq!(grid.neigh4(&Cartesian::new(0, 0)).len(), 2);
+    assert_eq!(grid.neigh8(&Cartesian::new(1, 0)).len(), 5);
+}
+
+#[test]
+fn test_flood_fill() {
+    let mut grid = Grid::from_str_with("..#..\n..#..\n###..\n", |c| c);
+    assert_eq!(
+        grid.flood_fill(&Cartesian::new(0, 0), |&c| c == '.', 'o'),
+        4
+    );
+    assert_eq!(grid, Grid::from_str_with("oo#..\noo#..\n###..\n", |c| c));
+
+    /* Starting on a cell not matching fills nothing */
+    assert_eq!(
+        grid.flood_fill(&Cartesian::new(2, 0), |&c| c == '.', 'o'),
+        0
+    );
+
+    /* A fill value matching the predicate must still terminate */
+    assert_eq!(
+        grid.flood_fill(&Cartesian::new(4, 0), |&c| c != '#', '.'),
+        6
+    );
+}
+
+#[test]
+fn test_grid_cell() {
+    use graph::astar_search;
+
+    let grid = Rc::new(Grid::from_str_with("...\n##.\n...\n", |c| c == '.'));
+    let start = Rc::new(GridCell::new(grid.clone(), Cartesian::new(0, 0)));
+    let goal = Rc::new(GridCell::new(grid.clone(), Cartesian::new(0, 2)));
+    let path = astar_search(start, goal).unwrap();
+    assert_eq!(path.len(), 7);
+    assert_eq!(path[2].position(), Cartesian::new(2, 0));
+}
+
+#[test]
+fn test_orientations() {
+    let grid = Grid::from_str_with("abc\ndef\n", |c| c);
+    let cw = grid.rotate_cw();
+    assert_eq!((cw.width(), cw.height()), (2, 3));
+    assert_eq!(cw, Grid::from_str_with("da\neb\nfc\n", |c| c));
+    assert_eq!(
+        grid.rotate_ccw(),
+        Grid::from_str_with("cf\nbe\nad\n", |c| c)
+    );
+    assert_eq!(
+        grid.flip_horizontal(),
+        Grid::from_str_with("cba\nfed\n", |c| c)
+    );
+    assert_eq!(
+        grid.flip_vertical(),
+        Grid::from_str_with("def\nabc\n", |c| c)
+    );
+    assert_eq!(grid.transpose(), Grid::from_str_with("ad\nbe\ncf\n", |c| c));
+
+    /* The operations compose as rotations and reflections should */
+    assert_eq!(cw.rotate_cw().rotate_cw().rotate_cw(), grid);
+    assert_eq!(cw.rotate_ccw(), grid);
+    assert_eq!(grid.transpose(), grid.flip_horizontal().rotate_ccw());
+
+    let orientations = grid.orientations();
+    assert_eq!(orientations.len(), 8);
+    for (i, a) in orientations.iter().enumerate() {
+        for b in &orientations[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+    assert!(orientations.contains(&grid.transpose()));
+    assert!(orientations.contains(&grid.flip_vertical()));
+}
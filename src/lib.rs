@@ -1,5 +1,13 @@
 pub extern crate clipboard;
 pub extern crate num;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 use clipboard::ClipboardContext;
 use clipboard::ClipboardProvider;
@@ -8,7 +16,10 @@ pub mod checkers;
 pub mod constants;
 pub mod containers;
 pub mod coord;
+pub mod disjoint_set;
+pub mod flow;
 pub mod graph;
+pub mod grid;
 pub mod math;
 pub mod representations;
 pub mod series;
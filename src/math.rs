@@ -78,7 +78,7 @@ pub fn divisors_proper(n: &usize) -> Vec<usize> {
 
 #[test]
 fn test_divisors_proper() {
-    assert_eq!(divisors_proper(&1), vec![]);
+    assert_eq!(divisors_proper(&1), Vec::<usize>::new());
     assert_eq!(divisors_proper(&2), vec![1]);
     assert_eq!(divisors_proper(&12), vec![1, 2, 3, 4, 6]);
 }